@@ -1,9 +1,7 @@
-use std::error::Error;
-use std::io::stdin;
-use std::fs::File;
-use std::io::BufReader;
-use std::io::BufRead;
-use clap::Parser;
+use std::ffi::OsString;
+use std::io::{self, BufRead, Write};
+use clap::{ArgGroup, CommandFactory, Parser};
+use cmdutil::{for_each_line, open, Defaults, DefaultsArgs, Error, FilesFromArgs, GenerateArgs, InputArgs, Result};
 
 #[derive(Parser)]
 #[derive(Debug)]
@@ -11,75 +9,287 @@ use clap::Parser;
 #[command(author = "Ben")]
 #[command(version = "1.0.0")]
 #[command(about = "Rust catr", long_about = None)]
+#[command(args_override_self = true)]
+#[command(group(ArgGroup::new("numbering").multiple(true)))]
 pub struct Config {
-    #[arg(value_name = "FILE NAMES", default_values_t = ["-".to_string()])]
+    #[arg(
+        value_name = "FILE NAMES",
+        default_values_t = ["-".to_string()],
+        conflicts_with_all = ["files0_from", "files_from"],
+    )]
     files: Vec<String>,
 
-    #[arg(short = 'n', long, default_value_t = false)]
+    #[arg(short = 'n', long, default_value_t = false, group = "numbering")]
     number_lines: bool,
 
-    #[arg(short = 'b', long, default_value_t = false)]
+    #[arg(short = 'b', long, default_value_t = false, group = "numbering")]
     number_nonblank_lines: bool,
+
+    /// Equivalent to -vET
+    #[arg(short = 'A', long, default_value_t = false)]
+    show_all: bool,
+
+    /// Equivalent to -vE
+    #[arg(short = 'e', default_value_t = false)]
+    show_nonprinting_ends: bool,
+
+    /// Display $ at the end of each line, and a CR before it as ^M
+    #[arg(short = 'E', long, default_value_t = false)]
+    show_ends: bool,
+
+    /// Equivalent to -vT
+    #[arg(short = 't', default_value_t = false)]
+    show_nonprinting_tabs: bool,
+
+    /// Display TAB characters as ^I
+    #[arg(short = 'T', long, default_value_t = false)]
+    show_tabs: bool,
+
+    /// Use ^ and M- notation, except for LFD and TAB
+    #[arg(short = 'v', long, default_value_t = false)]
+    show_nonprinting: bool,
+
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    files_from: FilesFromArgs,
+
+    #[command(flatten)]
+    defaults: DefaultsArgs,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
 }
 
-type WrapResult<T> = Result<T, Box<dyn Error>>;
+pub fn get_args() -> Result<Config> {
+    get_args_from(std::env::args_os())
+}
 
-pub fn get_args() -> WrapResult<Config> {
-    let config = Config::parse();
-    let files = config.files;
-    let number_lines = config.number_lines;
-    let number_nonblank_lines = config.number_nonblank_lines;
+pub fn get_args_from<I, T>(args: I) -> Result<Config>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let defaults = Defaults::load::<Config>("catr", args)?;
+    let config = Config::parse_from(defaults.args());
+    config.generate.exit_if_requested(Config::command())?;
+    config.defaults.exit_if_print_requested(&defaults, &config)?;
 
-    if number_lines && number_nonblank_lines {
-        return Err("the argument '--number-nonblank' (-b) cannot be used with '--number' (-n)".into());
+    if config.number_lines && config.number_nonblank_lines {
+        return Err(Error::Usage("the argument '--number-nonblank' (-b) cannot be used with '--number' (-n)".to_string()));
     }
 
-    Ok(Config {
-        files: files,
-        number_lines: number_lines,
-        number_nonblank_lines: number_nonblank_lines,
-    })
+    Ok(config)
+}
+
+/// Options for [`cat`], independent of the command-line parser.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    pub number_lines: bool,
+    pub number_nonblank_lines: bool,
+    pub show_nonprinting: bool,
+    pub show_ends: bool,
+    pub show_tabs: bool,
+}
+
+impl From<&Config> for Options {
+    fn from(config: &Config) -> Self {
+        Options {
+            number_lines: config.number_lines,
+            number_nonblank_lines: config.number_nonblank_lines,
+            show_nonprinting: config.show_nonprinting
+                || config.show_all
+                || config.show_nonprinting_ends
+                || config.show_nonprinting_tabs,
+            show_ends: config.show_ends || config.show_all || config.show_nonprinting_ends,
+            show_tabs: config.show_tabs || config.show_all || config.show_nonprinting_tabs,
+        }
+    }
 }
 
-pub fn run(config: Config) -> WrapResult<()> {
+pub fn run(config: Config) -> Result<()> {
+    let options = Options::from(&config);
+    let mut stdout = cmdutil::stdout();
     let mut line_number = 1;
-    for filename in config.files {
-        match open(&filename) {
-            Ok(file) => {
-                let reader = BufReader::new(file);
-                for line in reader.lines() {
-                    match line {
-                        Ok(line_content) => {
-                            if !config.number_nonblank_lines && !config.number_lines {
-                                println!("{}", line_content);
-                            } else {
-                                if !line_content.is_empty() {
-                                    println!("{} {}", line_number, line_content);
-                                    line_number += 1;
-                                } else {
-                                    if config.number_nonblank_lines {
-                                        println!();
-                                    } else {
-                                        println!("{}", line_number);
-                                        line_number += 1;
-                                    }
-                                }
-                            }
-                        },
-                        Err(err) => eprintln!("Failed to read line: {}", err)
-                    }
+    let mut failed = false;
+    for filename in config.files_from.names(&config.files)? {
+        let filename = match filename {
+            Ok(filename) => filename,
+            Err(err) => {
+                eprintln!("{}", err);
+                failed = true;
+                continue;
+            }
+        };
+        match open(&filename, config.input.decompress()) {
+            Ok(reader) => match cat(reader, &mut stdout, &options, line_number) {
+                Ok(next_line_number) => line_number = next_line_number,
+                Err(err) if err.is_broken_pipe() => return Err(err),
+                Err(err) => {
+                    eprintln!("Failed to read {}: {}", filename, err);
+                    failed = true;
                 }
             },
-            Err(err) => eprintln!("Failed to open {}: {}", filename, err)
+            Err(err) => {
+                eprintln!("Failed to open {}", err);
+                failed = true;
+            }
         }
+        stdout.flush()?;
     }
 
+    if failed {
+        return Err(Error::InputsFailed);
+    }
     Ok(())
 }
 
-fn open(filename: &str) -> WrapResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?)))
+/// Copies `reader` to `writer` byte for byte, numbering lines starting at
+/// `line_number`, and with the `show_*` options making the bytes in each line
+/// visible the way GNU cat does.
+///
+/// Returns the number for the next line so numbering can continue across inputs.
+pub fn cat<R: BufRead, W: Write>(mut reader: R, writer: &mut W, options: &Options, mut line_number: usize) -> Result<usize> {
+    let shows = options.show_nonprinting || options.show_ends || options.show_tabs;
+    let numbered = options.number_lines || options.number_nonblank_lines;
+    if !numbered && !shows {
+        io::copy(&mut reader, writer)?;
+        return Ok(line_number);
+    }
+
+    for_each_line(reader, writer, |line, writer| {
+        if numbered && line != b"\n" {
+            write!(writer, "{} ", line_number)?;
+            line_number += 1;
+        } else if options.number_lines {
+            write!(writer, "{}", line_number)?;
+            line_number += 1;
+        }
+        if shows {
+            show(line, writer, options)?;
+        } else {
+            writer.write_all(line)?;
+        }
+        Ok(true)
+    })?;
+
+    Ok(line_number)
+}
+
+/// Writes `line` with tabs as `^I`, the end marked by `$` and other
+/// nonprinting bytes in `^` and `M-` notation, as `options` ask.
+fn show<W: Write>(line: &[u8], writer: &mut W, options: &Options) -> io::Result<()> {
+    let (body, newline) = match line.strip_suffix(b"\n") {
+        Some(body) => (body, true),
+        None => (line, false),
+    };
+    // Like GNU cat, -E alone still marks the CR of a CRLF ending.
+    let (body, crlf) = match body.strip_suffix(b"\r") {
+        Some(body) if newline && options.show_ends && !options.show_nonprinting => (body, true),
+        _ => (body, false),
+    };
+
+    let mut out = Vec::with_capacity(line.len() + 2);
+    for &byte in body {
+        match byte {
+            b'\t' if options.show_tabs => out.extend_from_slice(b"^I"),
+            b'\t' => out.push(byte),
+            _ if !options.show_nonprinting => out.push(byte),
+            _ => {
+                let byte = if byte >= 128 {
+                    out.extend_from_slice(b"M-");
+                    byte - 128
+                } else {
+                    byte
+                };
+                match byte {
+                    0..=31 => out.extend_from_slice(&[b'^', byte + 64]),
+                    127 => out.extend_from_slice(b"^?"),
+                    _ => out.push(byte),
+                }
+            }
+        }
+    }
+    if crlf {
+        out.extend_from_slice(b"^M");
+    }
+    if newline {
+        if options.show_ends {
+            out.push(b'$');
+        }
+        out.push(b'\n');
+    }
+    writer.write_all(&out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cat, Options};
+    use std::io::Cursor;
+
+    const TEXT: &str = "one\n\ntwo\n";
+
+    fn run_cat(options: &Options, line_number: usize) -> (String, usize) {
+        let mut out = Vec::new();
+        let next = cat(Cursor::new(TEXT), &mut out, options, line_number).unwrap();
+        (String::from_utf8(out).unwrap(), next)
+    }
+
+    #[test]
+    fn test_cat_plain() {
+        let (out, next) = run_cat(&Options::default(), 1);
+        assert_eq!(out, TEXT);
+        assert_eq!(next, 1);
+    }
+
+    #[test]
+    fn test_cat_number_lines() {
+        let options = Options { number_lines: true, ..Options::default() };
+        let (out, next) = run_cat(&options, 5);
+        assert_eq!(out, "5 one\n6\n7 two\n");
+        assert_eq!(next, 8);
+    }
+
+    #[test]
+    fn test_cat_raw_bytes() {
+        let text = b"caf\xe9\r\n\xff\x00 no newline";
+        let mut out = Vec::new();
+        let options = Options { number_lines: true, ..Options::default() };
+        cat(Cursor::new(text), &mut out, &options, 1).unwrap();
+        assert_eq!(out, b"1 caf\xe9\r\n2 \xff\x00 no newline");
+    }
+
+    #[test]
+    fn test_cat_show_nonprinting() {
+        let text = b"x\x89\xff\x7f\x01\t\xa0\x0a\x8a";
+        let mut out = Vec::new();
+        let options = Options { show_nonprinting: true, ..Options::default() };
+        cat(Cursor::new(text), &mut out, &options, 1).unwrap();
+        assert_eq!(out, b"xM-^IM-^?^?^A\tM- \nM-^J");
+    }
+
+    #[test]
+    fn test_cat_show_ends_and_tabs() {
+        let text = b"a\tb\r\nc\rd\n\nend";
+        let show = |options: Options| {
+            let mut out = Vec::new();
+            cat(Cursor::new(text), &mut out, &options, 1).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(show(Options { show_ends: true, ..Options::default() }), "a\tb^M$\nc\rd$\n$\nend");
+        assert_eq!(show(Options { show_tabs: true, ..Options::default() }), "a^Ib\r\nc\rd\n\nend");
+        let all = Options { show_nonprinting: true, show_ends: true, show_tabs: true, ..Options::default() };
+        assert_eq!(show(all), "a^Ib^M$\nc^Md$\n$\nend");
+        let numbered = Options { number_nonblank_lines: true, show_ends: true, ..Options::default() };
+        assert_eq!(show(numbered), "1 a\tb^M$\n2 c\rd$\n$\n3 end");
+    }
+
+    #[test]
+    fn test_cat_number_nonblank_lines() {
+        let options = Options { number_nonblank_lines: true, ..Options::default() };
+        let (out, next) = run_cat(&options, 1);
+        assert_eq!(out, "1 one\n\n2 two\n");
+        assert_eq!(next, 3);
     }
 }
@@ -1,6 +1,5 @@
 fn main() {
-    if let Err(e) = catr::get_args().and_then(catr::run) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+    if let Err(err) = catr::get_args().and_then(catr::run) {
+        cmdutil::exit(err);
     }
 }
@@ -11,6 +11,17 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const A: &str = "tests/inputs/a.txt";
 const B: &str = "tests/inputs/b.txt";
 const C: &str = "tests/inputs/c.txt";
+const A_GZ: &str = "tests/inputs/a.txt.gz";
+const C_ZST: &str = "tests/inputs/c.txt.zst";
+const LATIN1: &str = "tests/inputs/latin1.txt";
+
+/// The tool under test, with neither the developer's own config file nor
+/// `CATR_OPTS` adding defaults.
+fn cmd() -> Result<Command, Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin(PRG)?;
+    cmd.env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR")).env_remove("CATR_OPTS");
+    Ok(cmd)
+}
 
 fn gen_bad_file() -> String {
     loop {
@@ -31,19 +42,55 @@ fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
 
-    Command::cargo_bin(PRG)?
+    cmd()?
         .arg(&bad)
         .assert()
-        .success()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
 
     Ok(())
 }
 
+#[test]
+fn continues_after_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/a.out.txt")?;
+
+    cmd()?
+        .args([&bad, A])
+        .assert()
+        .code(1)
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn dies_directory() -> TestResult {
+    cmd()?
+        .arg("tests")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("tests: Is a directory"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_number_and_number_nonblank() -> TestResult {
+    cmd()?
+        .args(["-n", "-b", A])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    let mut expected = fs::read_to_string(expected_file)?;
+    let expected = fs::read_to_string(expected_file)?;
 
-    Command::cargo_bin(PRG)?
+    cmd()?
         .args(args)
         .assert()
         .success()
@@ -117,7 +164,7 @@ fn run_stdin(input_file: &str, args: &[&str], expected_file: &str) -> TestResult
     let input = fs::read_to_string(input_file)?;
     let expected = fs::read_to_string(expected_file)?;
 
-    Command::cargo_bin(PRG)?
+    cmd()?
         .args(args)
         .write_stdin(input)
         .assert()
@@ -140,4 +187,114 @@ fn stdin_c_n() -> TestResult {
 #[test]
 fn stdin_c_b() -> TestResult {
     run_stdin(C, &["-", "-b"], "tests/expected/c.out.b.txt")
-}
\ No newline at end of file
+}
+
+#[test]
+fn gzip_file() -> TestResult {
+    run(&[A_GZ], "tests/expected/a.out.txt")
+}
+
+#[test]
+fn gzip_file_no_decompress() -> TestResult {
+    let expected = fs::read(A_GZ)?;
+
+    cmd()?
+        .args(["--no-decompress", A_GZ])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn latin1_bytes() -> TestResult {
+    cmd()?
+        .args(["-n", LATIN1])
+        .assert()
+        .success()
+        .stdout(&b"1 caf\xe9\r\n2 na\xefve\n"[..]);
+
+    Ok(())
+}
+
+#[test]
+fn stdin_zstd() -> TestResult {
+    let input = fs::read(C_ZST)?;
+    let expected = fs::read_to_string("tests/expected/c.out.n.txt")?;
+
+    cmd()?
+        .arg("-n")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn files_from_stdin() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/a.out.txt")?
+        + &fs::read_to_string("tests/expected/b.out.txt")?;
+
+    cmd()?
+        .args(["--files-from", "-"])
+        .write_stdin(format!("{A}\n{B}\n"))
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn files0_from_stdin() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/b.out.txt")?
+        + &fs::read_to_string("tests/expected/c.out.txt")?;
+
+    cmd()?
+        .args(["--files0-from", "-"])
+        .write_stdin(format!("{B}\0{C}\0"))
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn show_all_latin1() -> TestResult {
+    cmd()?
+        .args(["-A", LATIN1])
+        .assert()
+        .success()
+        .stdout("cafM-i^M$\nnaM-ove$\n");
+
+    cmd()?
+        .args(["-E", LATIN1])
+        .assert()
+        .success()
+        .stdout(&b"caf\xe9^M$\nna\xefve$\n"[..]);
+
+    Ok(())
+}
+
+#[test]
+fn show_tabs_and_nonprinting() -> TestResult {
+    cmd()?
+        .arg("-t")
+        .write_stdin("a\tb\x1b[0m\n")
+        .assert()
+        .success()
+        .stdout("a^Ib^[[0m\n");
+
+    cmd()?
+        .args(["-e", "-n"])
+        .write_stdin("a\tb\n\n")
+        .assert()
+        .success()
+        .stdout("1 a\tb$\n2$\n");
+
+    Ok(())
+}
@@ -0,0 +1,322 @@
+use std::{
+    env,
+    ffi::OsString,
+    fmt::Debug,
+    fs,
+    io::Write,
+    path::PathBuf,
+};
+
+use clap::{parser::ValueSource, Args, Command, CommandFactory, Id};
+use toml::{Table, Value};
+
+use crate::{Error, Result};
+
+/// Flags controlling the per-tool defaults read by [`Defaults::load`].
+#[derive(Args, Debug, Default)]
+#[group(skip)]
+pub struct DefaultsArgs {
+    /// Ignore the config file and the <TOOL>_OPTS environment variable
+    #[arg(long)]
+    no_config: bool,
+
+    /// Print the effective settings, with defaults merged in, and exit
+    #[arg(long)]
+    print_config: bool,
+}
+
+impl DefaultsArgs {
+    /// Prints where the defaults came from and the parsed `config`, then
+    /// exits the process. Returns when `--print-config` was not given.
+    pub fn exit_if_print_requested<C: Debug>(&self, defaults: &Defaults, config: &C) -> Result<()> {
+        if !self.print_config {
+            return Ok(());
+        }
+
+        let mut stdout = crate::stdout();
+        match &defaults.file {
+            Some(path) => writeln!(stdout, "# {}: {}", path.display(), defaults.file_args.join(" "))?,
+            None => writeln!(stdout, "# no config file")?,
+        }
+        writeln!(stdout, "# {}: {}", defaults.env_var, defaults.env_args.join(" "))?;
+        writeln!(stdout, "{config:#?}")?;
+        stdout.flush()?;
+        std::process::exit(0);
+    }
+}
+
+/// Default options for one tool, merged under its command-line arguments.
+///
+/// Defaults come from the `[<tool>]` section of
+/// `$XDG_CONFIG_HOME/rustutils/config.toml` and from the whitespace-separated
+/// `<TOOL>_OPTS` environment variable, which wins over the file. Each key
+/// becomes a flag, so `lines = 20` means `--lines=20` and `count = true` means
+/// `--count`. The flags are placed ahead of the user's arguments, and the
+/// tools let a later occurrence of a flag override an earlier one.
+///
+/// A default is dropped when the command line sets an argument that
+/// conflicts with it or belongs to the same `ArgGroup`, so a tool groups the
+/// flags that together make up one setting, like which counts `wcr` prints.
+/// The groups clap derives for each struct are not taken into account, and
+/// the shared argument structs opt out of theirs.
+#[derive(Debug, Default)]
+pub struct Defaults {
+    file: Option<PathBuf>,
+    file_args: Vec<String>,
+    env_var: String,
+    env_args: Vec<String>,
+    args: Vec<OsString>,
+}
+
+impl Defaults {
+    pub fn load<C: CommandFactory + Args>(tool: &str, args: impl IntoIterator<Item = impl Into<OsString>>) -> Result<Defaults> {
+        let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let env_var = format!("{}_OPTS", tool.to_uppercase());
+
+        // Packaging artifacts must not depend on the user's settings, and
+        // the generate flags refuse to be combined with any others.
+        let user_args = args.iter().skip(1).take_while(|arg| *arg != "--");
+        let skip = ["--no-config", "--generate-man", "--generate-completions"];
+        if user_args.clone().any(|arg| {
+            let arg = arg.to_string_lossy();
+            skip.iter().any(|flag| arg == *flag || arg.starts_with(&format!("{flag}=")))
+        }) {
+            return Ok(Defaults { env_var, args, ..Defaults::default() });
+        }
+
+        let file = config_file().filter(|path| path.is_file());
+        let file_args = match &file {
+            Some(path) => file_args(path, tool)?,
+            None => Vec::new(),
+        };
+        let env_args: Vec<String> = env::var(&env_var)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+
+        let defaults: Vec<&String> = file_args.iter().chain(&env_args).collect();
+        let merged = merge(&C::command(), C::group_id(), &args, &defaults);
+
+        Ok(Defaults {
+            file,
+            file_args,
+            env_var,
+            env_args,
+            args: merged,
+        })
+    }
+
+    /// The command line to parse: argv[0], the defaults, then the user's arguments.
+    pub fn args(&self) -> Vec<OsString> {
+        self.args.clone()
+    }
+}
+
+/// Places the `defaults` that the user's `args` leave in force between
+/// argv[0] and those arguments.
+fn merge(command: &Command, own_group: Option<Id>, args: &[OsString], defaults: &[&String]) -> Vec<OsString> {
+    let mut command = command.clone();
+    command.build();
+    let user_ids = set_ids(&command, args);
+    // The arguments that `id` conflicts with or shares a group with.
+    let related = |id: &str| -> Vec<String> {
+        let Some(arg) = command.get_arguments().find(|arg| arg.get_id() == id) else {
+            return Vec::new();
+        };
+        let grouped = command
+            .get_groups()
+            .filter(|group| Some(group.get_id()) != own_group.as_ref())
+            .filter(|group| group.get_args().any(|member| member == id))
+            .flat_map(|group| group.get_args());
+        command
+            .get_arg_conflicts_with(arg)
+            .into_iter()
+            .map(|arg| arg.get_id())
+            .chain(grouped)
+            .filter(|other| *other != id)
+            .map(|other| other.to_string())
+            .collect()
+    };
+    let overridden = |ids: &[String]| {
+        ids.iter().any(|id| {
+            related(id).iter().any(|other| user_ids.contains(other))
+                || user_ids.iter().any(|user| related(user).contains(id))
+        })
+    };
+
+    let argv0: Vec<OsString> = args.iter().take(1).cloned().collect();
+    let mut merged = argv0.clone();
+    for flag in flags(defaults.iter().copied()) {
+        let mut argv = argv0.clone();
+        argv.extend(flag.iter().map(OsString::from));
+        if !overridden(&set_ids(&command, &argv)) {
+            merged.extend(flag.into_iter().map(OsString::from));
+        }
+    }
+    merged.extend(args.iter().skip(1).cloned());
+    merged
+}
+
+/// The ids of the arguments `args` sets, as far as they parse.
+fn set_ids(command: &Command, args: &[OsString]) -> Vec<String> {
+    let Ok(matches) = command.clone().ignore_errors(true).try_get_matches_from(args) else {
+        return Vec::new();
+    };
+    matches
+        .ids()
+        .filter(|id| matches!(matches.value_source(id.as_str()), Some(ValueSource::CommandLine)))
+        .map(|id| id.to_string())
+        .collect()
+}
+
+/// Groups default arguments into flags, each followed by any separate values.
+fn flags<'a>(args: impl Iterator<Item = &'a String>) -> Vec<Vec<&'a String>> {
+    let mut flags: Vec<Vec<&String>> = Vec::new();
+    for arg in args {
+        match flags.last_mut() {
+            Some(flag) if !arg.starts_with('-') => flag.push(arg),
+            _ => flags.push(vec![arg]),
+        }
+    }
+    flags
+}
+
+fn config_file() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join("rustutils").join("config.toml"))
+}
+
+fn file_args(path: &PathBuf, tool: &str) -> Result<Vec<String>> {
+    let config_err = |message: String| Error::Config {
+        path: path.display().to_string(),
+        message,
+    };
+    let text = fs::read_to_string(path).map_err(|source| Error::Open {
+        path: path.display().to_string(),
+        source,
+    })?;
+    let table: Table = text.parse().map_err(|err: toml::de::Error| config_err(err.message().to_string()))?;
+
+    match table.get(tool) {
+        None => Ok(Vec::new()),
+        Some(Value::Table(section)) => section_args(section).map_err(config_err),
+        Some(_) => Err(config_err(format!("[{tool}] must be a table"))),
+    }
+}
+
+/// Turns `key = value` pairs into command-line flags.
+fn section_args(section: &Table) -> std::result::Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for (key, value) in section {
+        let flag = if key.chars().count() == 1 {
+            format!("-{key}")
+        } else {
+            format!("--{}", key.replace('_', "-"))
+        };
+        let values = match value {
+            Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            match value {
+                Value::Boolean(true) => args.push(flag.clone()),
+                Value::Boolean(false) => {}
+                Value::String(s) => args.push(flag_with_value(&flag, s)),
+                Value::Integer(_) | Value::Float(_) => args.push(flag_with_value(&flag, &value.to_string())),
+                _ => return Err(format!("unsupported value for '{key}'")),
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn flag_with_value(flag: &str, value: &str) -> String {
+    if flag.starts_with("--") {
+        format!("{flag}={value}")
+    } else {
+        format!("{flag}{value}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge, section_args, Defaults, DefaultsArgs};
+    use clap::{ArgGroup, Args, CommandFactory, Parser};
+    use std::ffi::OsString;
+    use toml::Table;
+
+    #[derive(Parser, Debug)]
+    #[command(group(ArgGroup::new("count").multiple(true)))]
+    struct Tool {
+        #[arg(short = 'n', long, group = "count")]
+        lines: Option<usize>,
+
+        #[arg(short = 'c', long, group = "count")]
+        bytes: Option<usize>,
+
+        #[arg(short, long, conflicts_with = "verbose")]
+        quiet: bool,
+
+        #[arg(short, long)]
+        verbose: bool,
+
+        #[arg(short, long)]
+        zero: bool,
+
+        #[command(flatten)]
+        defaults: DefaultsArgs,
+    }
+
+    fn merged(defaults: &[&str], args: &[&str]) -> Vec<String> {
+        let defaults: Vec<String> = defaults.iter().map(|arg| arg.to_string()).collect();
+        let args: Vec<OsString> = ["tool"].iter().chain(args).map(OsString::from).collect();
+        merge(&Tool::command(), Tool::group_id(), &args, &defaults.iter().collect::<Vec<_>>())
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect()
+    }
+
+    fn args(text: &str) -> Result<Vec<String>, String> {
+        let table: Table = text.parse().unwrap();
+        section_args(&table)
+    }
+
+    #[test]
+    fn test_section_args() {
+        assert_eq!(
+            args("lines = 20\ncount = true\nchars = false\nn = 5\nnumber_lines = true"),
+            Ok(vec![
+                "--count".to_string(),
+                "--lines=20".to_string(),
+                "-n5".to_string(),
+                "--number-lines".to_string(),
+            ])
+        );
+        assert_eq!(args("separator = ', '"), Ok(vec!["--separator=, ".to_string()]));
+        assert_eq!(args("files = ['a', 'b']"), Ok(vec!["--files=a".to_string(), "--files=b".to_string()]));
+        assert!(args("x = { y = 1 }").is_err());
+    }
+
+    #[test]
+    fn test_no_config_keeps_args() {
+        let defaults = Defaults::load::<Tool>("tool", ["tool", "--no-config", "-n", "3"]).unwrap();
+        assert_eq!(defaults.args(), ["tool", "--no-config", "-n", "3"]);
+    }
+
+    #[test]
+    fn test_merge_keeps_unrelated_defaults() {
+        assert_eq!(merged(&["--lines=5", "-z"], &["-v", "a"]), ["tool", "--lines=5", "-z", "-v", "a"]);
+        assert_eq!(merged(&["-n", "5"], &["-n", "2"]), ["tool", "-n", "5", "-n", "2"]);
+    }
+
+    #[test]
+    fn test_merge_drops_overridden_defaults() {
+        assert_eq!(merged(&["--lines=5", "-z"], &["-c", "9"]), ["tool", "-z", "-c", "9"]);
+        assert_eq!(merged(&["-n", "5", "-q"], &["--bytes=9", "-v"]), ["tool", "--bytes=9", "-v"]);
+        assert_eq!(merged(&["-v"], &["-q"]), ["tool", "-q"]);
+    }
+}
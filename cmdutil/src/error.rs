@@ -0,0 +1,109 @@
+use std::{fmt, io};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors shared by every tool, each mapped to a GNU-compatible exit status:
+///
+/// * `0` - success
+/// * `1` - an input or output failed ([`Error::Open`], [`Error::IsDirectory`],
+///   [`Error::InvalidFileName`], [`Error::Io`], [`Error::Invalid`],
+///   [`Error::InputsFailed`])
+/// * `2` - the command line or config file was invalid ([`Error::Usage`],
+///   [`Error::Config`]), matching the status clap uses for its own parse errors
+/// * `141` - standard output was closed early, e.g. by `| head`; this is the
+///   status a shell reports for a process killed by `SIGPIPE`
+#[derive(Debug)]
+pub enum Error {
+    /// An input or output file could not be opened.
+    Open { path: String, source: io::Error },
+    /// A directory was named where a file was expected.
+    IsDirectory { path: String },
+    /// An entry in a `--files0-from`/`--files-from` list is unusable.
+    /// `location` is the list name and entry number, e.g. `list.txt:3`.
+    InvalidFileName { location: String, reason: &'static str },
+    /// Reading or writing failed after the file was opened.
+    Io(io::Error),
+    /// The data being worked on, rather than the command line, was not
+    /// valid, e.g. text given to decode that is not in the encoding.
+    Invalid(String),
+    /// The command line was well-formed but the values were not valid.
+    Usage(String),
+    /// The config file holding default options could not be understood.
+    Config { path: String, message: String },
+    /// One or more inputs failed while the rest were still processed. Each
+    /// failure has already been reported on stderr.
+    InputsFailed,
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) | Error::Config { .. } => 2,
+            _ if self.is_broken_pipe() => 141,
+            _ => 1,
+        }
+    }
+
+    /// Whether a write failed because the reader of our output went away.
+    /// Tools stop at once instead of moving on to the next input.
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, Error::Io(err) if err.kind() == io::ErrorKind::BrokenPipe)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Open { path, source } => write!(f, "{path}: {source}"),
+            Error::IsDirectory { path } => write!(f, "{path}: Is a directory"),
+            Error::InvalidFileName { location, reason } => write!(f, "{location}: {reason}"),
+            Error::Io(err) => write!(f, "{err}"),
+            Error::Invalid(msg) => write!(f, "{msg}"),
+            Error::Usage(msg) => write!(f, "{msg}"),
+            Error::Config { path, message } => write!(f, "{path}: {message}"),
+            Error::InputsFailed => write!(f, "one or more inputs failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Open { source, .. } => Some(source),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// Reports `err` on stderr unless it was already reported or is a broken
+/// pipe, then exits the process with the error's exit code.
+pub fn exit(err: Error) -> ! {
+    if !matches!(err, Error::InputsFailed) && !err.is_broken_pipe() {
+        eprintln!("Error: {err}");
+    }
+    std::process::exit(err.exit_code());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use std::io;
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(Error::Usage("bad".to_string()).exit_code(), 2);
+        assert_eq!(Error::InputsFailed.exit_code(), 1);
+        assert_eq!(Error::Invalid("bad".to_string()).exit_code(), 1);
+        let err = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(Error::from(err).exit_code(), 1);
+        let err = io::Error::from(io::ErrorKind::BrokenPipe);
+        assert_eq!(Error::from(err).exit_code(), 141);
+    }
+}
@@ -0,0 +1,181 @@
+use std::{io::BufRead, slice};
+
+use clap::Args;
+
+use crate::{open, Error, Result};
+
+/// Flags for reading input names from a list file, for tools that take many
+/// files. Conflicts with the tool's positional file arguments are declared on
+/// those arguments.
+#[derive(Args, Debug, Default, Clone)]
+#[group(skip)]
+pub struct FilesFromArgs {
+    /// Read NUL-separated input names from FILE ("-" for stdin)
+    #[arg(long, value_name = "FILE", conflicts_with = "files_from")]
+    files0_from: Option<String>,
+
+    /// Read newline-separated input names from FILE ("-" for stdin)
+    #[arg(long, value_name = "FILE")]
+    files_from: Option<String>,
+}
+
+impl FilesFromArgs {
+    /// Returns the input names, streamed from the list file if one was given
+    /// and taken from `files` otherwise.
+    pub fn names<'a>(&self, files: &'a [String]) -> Result<FileNames<'a>> {
+        let (list, separator) = match (&self.files0_from, &self.files_from) {
+            (Some(list), _) => (list, b'\0'),
+            (None, Some(list)) => (list, b'\n'),
+            (None, None) => return Ok(FileNames::Args(files.iter())),
+        };
+
+        Ok(FileNames::List {
+            reader: open(list, false)?,
+            list: list.to_string(),
+            separator,
+            index: 0,
+        })
+    }
+}
+
+/// Input names, yielding an error for each unusable entry in a list file so
+/// the caller can report it and carry on. A list file that cannot be read
+/// yields its error once and then ends.
+pub enum FileNames<'a> {
+    Args(slice::Iter<'a, String>),
+    List {
+        reader: Box<dyn BufRead>,
+        list: String,
+        separator: u8,
+        index: usize,
+    },
+}
+
+impl Iterator for FileNames<'_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (reader, list, separator, index) = match self {
+            FileNames::Args(files) => return files.next().cloned().map(Ok),
+            FileNames::List { reader, list, separator, index } => (reader, list, *separator, index),
+        };
+
+        let mut name = Vec::new();
+        match reader.read_until(separator, &mut name) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(source) => {
+                let err = Error::Open {
+                    path: list.clone(),
+                    source,
+                };
+                // Reading again would most likely fail the same way forever.
+                *self = FileNames::Args(<&[String]>::default().iter());
+                return Some(Err(err));
+            }
+        }
+        *index += 1;
+
+        if name.last() == Some(&separator) {
+            name.pop();
+        }
+        let invalid = |reason| Error::InvalidFileName {
+            location: format!("{list}:{index}"),
+            reason,
+        };
+        if name.is_empty() {
+            return Some(Err(invalid("invalid zero-length file name")));
+        }
+        // Standard input is already taken up by the list itself.
+        if list == "-" && name == b"-" {
+            return Some(Err(invalid("when reading file names from standard input, no file name of '-' allowed")));
+        }
+        Some(String::from_utf8(name).map_err(|_| invalid("file name is not valid UTF-8")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileNames;
+    use std::io::{self, BufReader, Cursor, Read};
+
+    fn list(data: &'static [u8], separator: u8) -> Vec<Result<String, String>> {
+        named_list("list", data, separator)
+    }
+
+    fn named_list(name: &str, data: &'static [u8], separator: u8) -> Vec<Result<String, String>> {
+        let names = FileNames::List {
+            reader: Box::new(Cursor::new(data)),
+            list: name.to_string(),
+            separator,
+            index: 0,
+        };
+        names.map(|name| name.map_err(|err| err.to_string())).collect()
+    }
+
+    #[test]
+    fn test_args() {
+        let files = vec!["a".to_string(), "-".to_string()];
+        let names: Vec<_> = FileNames::Args(files.iter()).map(Result::unwrap).collect();
+        assert_eq!(names, files);
+    }
+
+    #[test]
+    fn test_nul_separated() {
+        assert_eq!(
+            list(b"a b\0c\nd\0", b'\0'),
+            vec![Ok("a b".to_string()), Ok("c\nd".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_newline_separated_without_final_newline() {
+        assert_eq!(list(b"a\nb", b'\n'), vec![Ok("a".to_string()), Ok("b".to_string())]);
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        assert_eq!(
+            list(b"a\0\0\xff\0b\0", b'\0'),
+            vec![
+                Ok("a".to_string()),
+                Err("list:2: invalid zero-length file name".to_string()),
+                Err("list:3: file name is not valid UTF-8".to_string()),
+                Ok("b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stdin_in_stdin_list() {
+        assert_eq!(
+            named_list("-", b"a\0-\0", b'\0'),
+            vec![
+                Ok("a".to_string()),
+                Err("-:2: when reading file names from standard input, no file name of '-' allowed".to_string()),
+            ]
+        );
+        assert_eq!(list(b"-\n", b'\n'), vec![Ok("-".to_string())]);
+    }
+
+    /// A reader whose every read fails.
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("Input/output error"))
+        }
+    }
+
+    #[test]
+    fn test_read_error_ends_list() {
+        let names = FileNames::List {
+            reader: Box::new(BufReader::new(Failing)),
+            list: "list".to_string(),
+            separator: b'\n',
+            index: 0,
+        };
+        let names: Vec<_> = names.take(3).map(|name| name.map_err(|err| err.to_string())).collect();
+        assert_eq!(names, vec![Err("list: Input/output error".to_string())]);
+    }
+}
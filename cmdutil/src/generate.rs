@@ -0,0 +1,40 @@
+use std::io::Write;
+
+use clap::{Args, Command};
+use clap_complete::Shell;
+
+use crate::Result;
+
+/// Hidden flags, flattened into each tool's parser, that print packaging
+/// artifacts generated from the tool's own argument definitions.
+#[derive(Args, Debug, Default)]
+#[group(skip)]
+pub struct GenerateArgs {
+    /// Print a completion script for SHELL and exit
+    #[arg(long, value_name = "SHELL", hide = true, exclusive = true)]
+    generate_completions: Option<Shell>,
+
+    /// Print a roff man page and exit
+    #[arg(long, hide = true, exclusive = true)]
+    generate_man: bool,
+}
+
+impl GenerateArgs {
+    /// Writes the requested artifact for `cmd` to stdout and exits the process,
+    /// the same way clap handles `--help`. Returns when neither flag was given.
+    pub fn exit_if_requested(&self, mut cmd: Command) -> Result<()> {
+        if self.generate_completions.is_none() && !self.generate_man {
+            return Ok(());
+        }
+
+        let mut stdout = crate::stdout();
+        if let Some(shell) = self.generate_completions {
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut stdout);
+        } else {
+            clap_mangen::Man::new(cmd).render(&mut stdout)?;
+        }
+        stdout.flush()?;
+        std::process::exit(0);
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    fs::File,
+    io::{self, stdin, BufRead, BufReader, Cursor, IsTerminal, Read, Write},
+};
+
+use clap::Args;
+
+use crate::{Error, Result};
+
+/// Input flags shared by every tool that reads files.
+#[derive(Args, Debug, Default, Clone)]
+#[group(skip)]
+pub struct InputArgs {
+    /// Read compressed inputs as raw bytes instead of decompressing them
+    #[arg(long)]
+    no_decompress: bool,
+}
+
+impl InputArgs {
+    pub fn decompress(&self) -> bool {
+        !self.no_decompress
+    }
+}
+
+/// Opens `filename` for buffered reading, treating "-" as standard input.
+///
+/// Directories are rejected up front rather than failing on the first read.
+/// With `decompress`, gzip, bzip2, xz and zstd data is detected by its magic
+/// bytes and decoded transparently, whether it comes from a file or stdin.
+pub fn open(filename: &str, decompress: bool) -> Result<Box<dyn BufRead>> {
+    if filename == "-" {
+        // Compressed data never comes from a terminal, and sniffing would
+        // block until the user had typed enough bytes.
+        let decompress = decompress && !stdin().is_terminal();
+        return wrap(Box::new(stdin()), decompress).map_err(Error::Io);
+    }
+
+    let open_err = |source| Error::Open {
+        path: filename.to_string(),
+        source,
+    };
+    let file = File::open(filename).map_err(open_err)?;
+    if file.metadata().map_err(open_err)?.is_dir() {
+        return Err(Error::IsDirectory {
+            path: filename.to_string(),
+        });
+    }
+
+    wrap(Box::new(file), decompress).map_err(open_err)
+}
+
+/// Hands each line of `reader`, newline included, to `each` along with
+/// `writer`, until `each` returns `false`. `writer` is flushed whenever
+/// the input read so far is used up, before reading on might have to wait,
+/// so output keeps pace with slow input such as `tail -f` while still being
+/// written in large blocks when input is plentiful.
+pub fn for_each_line<R, W, F>(mut reader: R, writer: &mut W, mut each: F) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+    F: FnMut(&[u8], &mut W) -> io::Result<bool>,
+{
+    let mut line = Vec::new();
+    loop {
+        let buffered = reader.fill_buf()?;
+        if buffered.is_empty() {
+            // The last line may have no newline.
+            if !line.is_empty() {
+                each(&line, writer)?;
+            }
+            return Ok(());
+        }
+        let len = buffered.iter().position(|&b| b == b'\n').map_or(buffered.len(), |pos| pos + 1);
+        let drained = len == buffered.len();
+        line.extend_from_slice(&buffered[..len]);
+        reader.consume(len);
+        if line.ends_with(b"\n") {
+            if !each(&line, writer)? {
+                return Ok(());
+            }
+            line.clear();
+        }
+        if drained {
+            writer.flush()?;
+        }
+    }
+}
+
+/// The longest magic number among the supported formats (bzip2 with the
+/// magic of its first block).
+const MAGIC_LEN: usize = 10;
+
+#[derive(Debug, PartialEq)]
+enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    fn detect(magic: &[u8]) -> Option<Compression> {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if Self::is_bzip2(magic) {
+            Some(Compression::Bzip2)
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// "BZh" and a block size of 1-9 are followed by the magic of either a
+    /// block (the digits of pi) or, for an empty stream, the end of stream.
+    fn is_bzip2(magic: &[u8]) -> bool {
+        match magic {
+            [b'B', b'Z', b'h', b'1'..=b'9', block @ ..] => {
+                block.starts_with(&BZIP2_BLOCK) || block.starts_with(&BZIP2_END)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `bytes` are too few to tell, being the start of the magic
+    /// bytes of one of the formats.
+    fn is_partial(bytes: &[u8]) -> bool {
+        let mut bytes = bytes[..bytes.len().min(MAGIC_LEN)].to_vec();
+        // Any bzip2 block size will do, so compare as if it were 9.
+        if let Some(size @ b'1'..=b'9') = bytes.get_mut(3) {
+            *size = b'9';
+        }
+        let magics: [&[u8]; 5] = [
+            &[0x1f, 0x8b],
+            &[b"BZh9".as_slice(), &BZIP2_BLOCK].concat(),
+            &[b"BZh9".as_slice(), &BZIP2_END].concat(),
+            &[0xfd, b'7', b'z', b'X', b'Z', 0x00],
+            &[0x28, 0xb5, 0x2f, 0xfd],
+        ];
+        magics.iter().any(|magic| bytes.len() < magic.len() && magic.starts_with(&bytes))
+    }
+}
+
+const BZIP2_BLOCK: [u8; 6] = [0x31, 0x41, 0x59, 0x26, 0x53, 0x59];
+const BZIP2_END: [u8; 6] = [0x17, 0x72, 0x45, 0x38, 0x50, 0x90];
+
+fn wrap(inner: Box<dyn Read>, decompress: bool) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(inner);
+    if !decompress {
+        return Ok(Box::new(reader));
+    }
+
+    // Peek at whatever has arrived. A pipe may deliver a short line and then
+    // nothing for a while, so only wait for more while the bytes so far
+    // could still be the start of a magic number.
+    let buffered = reader.fill_buf()?;
+    if !Compression::is_partial(buffered) {
+        let compression = Compression::detect(buffered);
+        return decoder(reader, compression);
+    }
+    let mut magic = buffered.to_vec();
+    reader.consume(magic.len());
+    while Compression::is_partial(&magic) {
+        let buffered = reader.fill_buf()?;
+        if buffered.is_empty() {
+            break;
+        }
+        let len = buffered.len().min(MAGIC_LEN - magic.len());
+        magic.extend_from_slice(&buffered[..len]);
+        reader.consume(len);
+    }
+    let compression = Compression::detect(&magic);
+    // Put the magic bytes back in front of the stream.
+    decoder(Cursor::new(magic).chain(reader), compression)
+}
+
+/// Decodes `reader` as `compression`, or passes it through if it is `None`.
+fn decoder<R: BufRead + 'static>(reader: R, compression: Option<Compression>) -> io::Result<Box<dyn BufRead>> {
+    Ok(match compression {
+        None => Box::new(reader),
+        Some(Compression::Gzip) => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(reader))),
+        Some(Compression::Bzip2) => Box::new(BufReader::new(bzip2::read::MultiBzDecoder::new(reader))),
+        Some(Compression::Xz) => Box::new(BufReader::new(xz2::read::XzDecoder::new_multi_decoder(reader))),
+        Some(Compression::Zstd) => Box::new(BufReader::new(zstd::stream::read::Decoder::new(reader)?)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{for_each_line, open, wrap, Compression};
+    use crate::Error;
+    use std::io::{self, BufRead, Cursor, Read, Write};
+
+    const TEXT: &[u8] = b"first line\nsecond line\n";
+
+    fn read_wrapped(data: Vec<u8>, decompress: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        wrap(Box::new(Cursor::new(data)), decompress)
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_open_directory() {
+        let err = open(env!("CARGO_MANIFEST_DIR"), true).err().unwrap();
+        assert!(matches!(err, Error::IsDirectory { .. }));
+        assert!(err.to_string().ends_with(": Is a directory"));
+    }
+
+    #[test]
+    fn test_open_missing() {
+        let err = open("no/such/file", true).err().unwrap();
+        assert!(matches!(err, Error::Open { .. }));
+        assert!(err.to_string().starts_with("no/such/file: "));
+    }
+
+    #[test]
+    fn test_open_file() {
+        let mut reader = open(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"), true).unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "[package]\n");
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Compression::detect(b"\x1f\x8b\x08"), Some(Compression::Gzip));
+        assert_eq!(Compression::detect(b"BZh91AY&SY"), Some(Compression::Bzip2));
+        assert_eq!(Compression::detect(b"BZh61AY&SY\x8b\x13"), Some(Compression::Bzip2));
+        assert_eq!(Compression::detect(b"BZh9\x17\x72\x45\x38\x50\x90"), Some(Compression::Bzip2));
+        assert_eq!(Compression::detect(b"\xfd7zXZ\x00"), Some(Compression::Xz));
+        assert_eq!(Compression::detect(b"\x28\xb5\x2f\xfd"), Some(Compression::Zstd));
+        assert_eq!(Compression::detect(b"BZ"), None);
+        assert_eq!(Compression::detect(b"BZhello"), None);
+        assert_eq!(Compression::detect(b"BZh01AY&SY"), None);
+        assert_eq!(Compression::detect(b""), None);
+    }
+
+    #[test]
+    fn test_wrap_plain() {
+        assert_eq!(read_wrapped(TEXT.to_vec(), true), TEXT);
+        assert_eq!(read_wrapped(b"BZ".to_vec(), true), b"BZ");
+        assert_eq!(read_wrapped(b"BZhello\n".to_vec(), true), b"BZhello\n");
+    }
+
+    /// Delivers its chunks one read at a time, like a pipe fed slowly, and
+    /// fails if read past them.
+    struct Trickle(Vec<&'static [u8]>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            assert!(!self.0.is_empty(), "read past the data that has arrived");
+            let chunk = self.0.remove(0);
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    #[test]
+    fn test_wrap_does_not_wait() {
+        let mut line = String::new();
+        wrap(Box::new(Trickle(vec![b"hi\n"])), true).unwrap().read_line(&mut line).unwrap();
+        assert_eq!(line, "hi\n");
+
+        // Bytes that could start a magic number are worth waiting for.
+        let mut line = String::new();
+        let mut reader = wrap(Box::new(Trickle(vec![b"B", b"Z", b"!\n"])), true).unwrap();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "BZ!\n");
+    }
+
+    #[test]
+    fn test_wrap_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(TEXT).unwrap();
+        let mut data = encoder.finish().unwrap();
+        // Concatenated members decode as one stream, like `zcat`.
+        data.extend_from_slice(&data.clone());
+
+        assert_eq!(read_wrapped(data.clone(), true), [TEXT, TEXT].concat());
+        assert_eq!(read_wrapped(data.clone(), false), data);
+    }
+
+    #[test]
+    fn test_wrap_bzip2() {
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(TEXT).unwrap();
+        assert_eq!(read_wrapped(encoder.finish().unwrap(), true), TEXT);
+
+        let empty = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        assert_eq!(read_wrapped(empty.finish().unwrap(), true), b"");
+    }
+
+    #[test]
+    fn test_wrap_xz() {
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(TEXT).unwrap();
+        assert_eq!(read_wrapped(encoder.finish().unwrap(), true), TEXT);
+    }
+
+    #[test]
+    fn test_wrap_zstd() {
+        let data = zstd::encode_all(TEXT, 0).unwrap();
+        assert_eq!(read_wrapped(data, true), TEXT);
+    }
+
+    /// Records what was written at each flush.
+    #[derive(Default)]
+    struct Flushes {
+        pending: Vec<u8>,
+        flushed: Vec<Vec<u8>>,
+    }
+
+    impl Write for Flushes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.pending.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed.push(std::mem::take(&mut self.pending));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_for_each_line() {
+        let mut out = Flushes::default();
+        let reader = io::BufReader::new(Trickle(vec![b"a\nb", b"c\n", b"d\ne\n", b"f", b""]));
+        for_each_line(reader, &mut out, |line, out| out.write_all(line).map(|_| true)).unwrap();
+        assert_eq!(out.flushed, [b"a\n".as_slice(), b"bc\n", b"d\ne\n", b""]);
+        assert_eq!(out.pending, b"f");
+
+        let mut out = Flushes::default();
+        let mut count = 0;
+        for_each_line(Cursor::new(TEXT), &mut out, |line, out| {
+            count += 1;
+            out.write_all(line).map(|_| false)
+        })
+        .unwrap();
+        assert_eq!((count, out.pending), (1, b"first line\n".to_vec()));
+    }
+}
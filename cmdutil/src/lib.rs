@@ -0,0 +1,15 @@
+//! Helpers shared by the command-line tools in this workspace.
+
+mod defaults;
+mod error;
+mod files;
+mod generate;
+mod input;
+mod output;
+
+pub use defaults::{Defaults, DefaultsArgs};
+pub use error::{exit, Error, Result};
+pub use files::{FileNames, FilesFromArgs};
+pub use generate::GenerateArgs;
+pub use input::{for_each_line, open, InputArgs};
+pub use output::{append, create, stdout, AtomicFile};
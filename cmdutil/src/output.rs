@@ -0,0 +1,182 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, StdoutLock, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{Error, Result};
+
+/// Locked, buffered standard output. Callers must `flush` it before exiting
+/// so that write errors such as a closed pipe are reported.
+pub fn stdout() -> BufWriter<StdoutLock<'static>> {
+    BufWriter::new(io::stdout().lock())
+}
+
+/// Creates the output sink: the named file if given, otherwise standard output.
+pub fn create(filename: Option<&str>) -> Result<Box<dyn Write>> {
+    match filename {
+        Some(name) => {
+            let file = File::create(name).map_err(|source| Error::Open {
+                path: name.to_string(),
+                source,
+            })?;
+            Ok(Box::new(BufWriter::new(file)))
+        }
+        None => Ok(Box::new(stdout())),
+    }
+}
+
+/// Opens the named file for appending, creating it if needed.
+pub fn append(filename: &str) -> Result<Box<dyn Write>> {
+    let file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(filename)
+        .map_err(|source| Error::Open {
+            path: filename.to_string(),
+            source,
+        })?;
+    Ok(Box::new(BufWriter::new(file)))
+}
+
+/// A file written under a temporary name in the same directory and renamed
+/// over the real one by [`AtomicFile::commit`], so readers see either the
+/// old contents or the new, never a partial write. Dropping it without
+/// committing removes the temporary file and leaves the target alone.
+pub struct AtomicFile {
+    path: String,
+    temp: PathBuf,
+    file: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    pub fn create(filename: &str) -> Result<AtomicFile> {
+        let open_err = |source| Error::Open {
+            path: filename.to_string(),
+            source,
+        };
+        let target = Path::new(filename);
+        let name = target
+            .file_name()
+            .ok_or_else(|| open_err(io::Error::new(io::ErrorKind::InvalidInput, "not a file name")))?;
+        let dir = match target.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        let mut attempt = 0;
+        loop {
+            let temp = dir.join(format!(".{}.{}.{attempt}.tmp", name.to_string_lossy(), process::id()));
+            match OpenOptions::new().write(true).create_new(true).open(&temp) {
+                Ok(file) => {
+                    // Keep the mode of the file being replaced. This is best
+                    // effort: the new contents matter more than the mode.
+                    if let Ok(metadata) = fs::metadata(target) {
+                        let _ = file.set_permissions(metadata.permissions());
+                    }
+                    return Ok(AtomicFile {
+                        path: filename.to_string(),
+                        temp,
+                        file: Some(BufWriter::new(file)),
+                    });
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+                Err(err) => return Err(open_err(err)),
+            }
+        }
+    }
+
+    /// Flushes everything to disk and moves the file into place.
+    pub fn commit(mut self) -> Result<()> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let result = file
+            .into_inner()
+            .map_err(|err| err.into_error())
+            .and_then(|file| file.sync_all())
+            .and_then(|()| fs::rename(&self.temp, &self.path));
+        if let Err(source) = result {
+            let _ = fs::remove_file(&self.temp);
+            return Err(Error::Open {
+                path: self.path.clone(),
+                source,
+            });
+        }
+        Ok(())
+    }
+
+    fn file(&mut self) -> &mut BufWriter<File> {
+        self.file.as_mut().expect("AtomicFile used after commit")
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file().flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.temp);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append, AtomicFile};
+    use std::{fs, io::Write};
+
+    #[test]
+    fn test_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.txt");
+        let path = path.to_str().unwrap();
+        for line in ["one\n", "two\n"] {
+            let mut out = append(path).unwrap();
+            out.write_all(line.as_bytes()).unwrap();
+            out.flush().unwrap();
+        }
+        assert_eq!(fs::read_to_string(path).unwrap(), "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_atomic_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.txt");
+        fs::write(&path, "old\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let mut file = AtomicFile::create(path).unwrap();
+        file.write_all(b"new\n").unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "old\n");
+        file.commit().unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "new\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_atomic_file_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.txt");
+        let mut file = AtomicFile::create(path.to_str().unwrap()).unwrap();
+        file.write_all(b"partial").unwrap();
+        drop(file);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_atomic_file_missing_dir() {
+        let err = AtomicFile::create("no/such/dir/file").err().unwrap();
+        assert!(err.to_string().starts_with("no/such/dir/file: "));
+    }
+}
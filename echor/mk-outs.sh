@@ -6,4 +6,7 @@ OUTDIR="tests/expected"
 echo "Hello there" > $OUTDIR/hello1.txt
 echo "Hello" "there" > $OUTDIR/hello2.txt
 echo -n "Hello  there" > $OUTDIR/hello1.n.txt
-echo -n "Hello" "there" > $OUTDIR/hello2.n.txt
\ No newline at end of file
+echo -n "Hello" "there" > $OUTDIR/hello2.n.txt
+echo -e 'tab\there' 'oct\0101\x42' > $OUTDIR/escapes.e.txt
+echo -e 'stop\c' 'never' > $OUTDIR/escapes.c.txt
+echo -E 'tab\there' > $OUTDIR/escapes.E.txt
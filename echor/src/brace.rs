@@ -0,0 +1,137 @@
+/// Expands braces in `word` the way bash does, returning the resulting words
+/// in order.
+///
+/// Handles lists such as `{a,b,c}` (which may nest), integer ranges such as
+/// `{1..10}` and `{01..100..5}` (zero padded when either end is), and
+/// letter ranges such as `{a..z}`. Braces that form neither, like `{}`,
+/// `{a}` or `${VAR}`, are left as they are.
+pub fn brace_expand(word: &str) -> Vec<String> {
+    for (start, _) in word.match_indices('{') {
+        if word[..start].ends_with('$') {
+            continue;
+        }
+        let Some((end, commas)) = closing_brace(&word[start + 1..]) else {
+            continue;
+        };
+        let end = start + 1 + end;
+        let inner = &word[start + 1..end];
+
+        let alternatives: Vec<String> = if commas.is_empty() {
+            match sequence(inner) {
+                Some(sequence) => sequence.collect(),
+                None => continue,
+            }
+        } else {
+            let mut alternatives = Vec::new();
+            let mut from = 0;
+            for comma in commas.into_iter().chain([inner.len()]) {
+                alternatives.extend(brace_expand(&inner[from..comma]));
+                from = comma + 1;
+            }
+            alternatives
+        };
+
+        let preamble = &word[..start];
+        let postscripts = brace_expand(&word[end + 1..]);
+        return alternatives
+            .iter()
+            .flat_map(|alternative| postscripts.iter().map(move |post| format!("{preamble}{alternative}{post}")))
+            .collect();
+    }
+
+    vec![word.to_string()]
+}
+
+/// Finds the `}` matching a `{` whose contents start `text`, along with the
+/// positions of the commas that are not inside nested braces.
+fn closing_brace(text: &str) -> Option<(usize, Vec<usize>)> {
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    for (idx, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some((idx, commas)),
+            '}' => depth -= 1,
+            ',' if depth == 0 => commas.push(idx),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Lazily expands the inside of `{START..END[..STEP]}`, or returns `None` if
+/// `inner` is not a range.
+pub fn sequence(inner: &str) -> Option<Box<dyn Iterator<Item = String>>> {
+    let parts: Vec<&str> = inner.split("..").collect();
+    let (start, end, step) = match parts[..] {
+        [start, end] => (start, end, 1),
+        [start, end, step] => (start, end, step.parse::<i64>().ok()?),
+        _ => return None,
+    };
+    // Like bash, the sign of the step doesn't matter and zero means one.
+    let step = step.unsigned_abs().max(1) as usize;
+
+    if let (Ok(first), Ok(last)) = (start.parse::<i64>(), end.parse::<i64>()) {
+        let padded = |s: &str| s.trim_start_matches('-').len() > 1 && s.trim_start_matches('-').starts_with('0');
+        let width = if padded(start) || padded(end) { start.len().max(end.len()) } else { 0 };
+        return Some(Box::new(range(first, last, step).map(move |n| format!("{n:0width$}"))));
+    }
+
+    let (mut first, mut last) = (start.chars(), end.chars());
+    match (first.next(), first.next(), last.next(), last.next()) {
+        (Some(first), None, Some(last), None) if first.is_ascii_alphabetic() && last.is_ascii_alphabetic() => Some(
+            Box::new(
+                range(first as i64, last as i64, step)
+                    .filter_map(|code| char::from_u32(code as u32))
+                    .map(String::from),
+            ),
+        ),
+        _ => None,
+    }
+}
+
+fn range(first: i64, last: i64, step: usize) -> Box<dyn Iterator<Item = i64>> {
+    if first <= last {
+        Box::new((first..=last).step_by(step))
+    } else {
+        Box::new((last..=first).rev().step_by(step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::brace_expand;
+
+    fn check(word: &str, expected: &str) {
+        assert_eq!(brace_expand(word).join(" "), expected, "expanding {word:?}");
+    }
+
+    #[test]
+    fn test_brace_lists() {
+        check("{a,b,c}", "a b c");
+        check("file.{txt,md}.bak", "file.txt.bak file.md.bak");
+        check("{a,b}{1,2}", "a1 a2 b1 b2");
+        check("x{a,{b,c}d,}y", "xay xbdy xcdy xy");
+    }
+
+    #[test]
+    fn test_brace_ranges() {
+        check("{1..5}", "1 2 3 4 5");
+        check("{3..-1}", "3 2 1 0 -1");
+        check("{01..20..5}", "01 06 11 16");
+        check("{10..1..-3}", "10 7 4 1");
+        check("{a..e}", "a b c d e");
+        check("{e..a..2}", "e c a");
+        check("test{1..3}.txt", "test1.txt test2.txt test3.txt");
+    }
+
+    #[test]
+    fn test_brace_literals() {
+        check("{}", "{}");
+        check("{a}", "{a}");
+        check("${HOME}", "${HOME}");
+        check("{a,b", "{a,b");
+        check("{1..b}", "{1..b}");
+        check("{{a,b}}", "{a} {b}");
+    }
+}
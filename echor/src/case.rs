@@ -0,0 +1,201 @@
+use clap::ValueEnum;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// A case for `--case`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Case {
+    /// ALL CAPITALS
+    Upper,
+    /// all lowercase
+    Lower,
+    /// Each Word Capitalized
+    Title,
+    /// words_joined_by_underscores
+    Snake,
+    /// words-joined-by-hyphens
+    Kebab,
+    /// wordsJoinedLikeThis
+    Camel,
+}
+
+/// A Unicode normalization form for `--normalize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Normalization {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+/// Language-specific case rules. Only the Turkic languages, with their
+/// dotted and dotless i, differ from the default mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    Root,
+    Turkic,
+}
+
+impl Locale {
+    /// The rules for a locale name such as `tr`, `az_AZ` or `tr_TR.UTF-8`.
+    pub fn from_name(name: &str) -> Locale {
+        let language = name.split(['_', '-', '.', '@']).next().unwrap_or_default();
+        match language.to_ascii_lowercase().as_str() {
+            "tr" | "az" => Locale::Turkic,
+            _ => Locale::Root,
+        }
+    }
+
+    fn upper(self, text: &str) -> String {
+        match self {
+            Locale::Root => text.to_uppercase(),
+            Locale::Turkic => text.replace('i', "İ").to_uppercase(),
+        }
+    }
+
+    fn lower(self, text: &str) -> String {
+        match self {
+            Locale::Root => text.to_lowercase(),
+            // İ, or I with a combining dot, is i; a bare I is dotless.
+            Locale::Turkic => text.replace("I\u{307}", "i").replace('İ', "i").replace('I', "ı").to_lowercase(),
+        }
+    }
+
+    /// Title cases the first character of `word` and lowercases the rest.
+    fn capitalize(self, word: &str) -> String {
+        let mut chars = word.chars();
+        let Some(first) = chars.next() else {
+            return String::new();
+        };
+        let mut out = match first {
+            'ß' => "Ss".to_string(),
+            // The digraphs have a title case form of their own.
+            '\u{1c4}'..='\u{1c6}' => "\u{1c5}".to_string(),
+            '\u{1c7}'..='\u{1c9}' => "\u{1c8}".to_string(),
+            '\u{1ca}'..='\u{1cc}' => "\u{1cb}".to_string(),
+            '\u{1f1}'..='\u{1f3}' => "\u{1f2}".to_string(),
+            first => self.upper(first.encode_utf8(&mut [0; 4])),
+        };
+        out.push_str(&self.lower(chars.as_str()));
+        out
+    }
+}
+
+/// Converts `text` to `case` with the full Unicode case mappings, so `ß`
+/// uppercases to `SS`. Snake, kebab and camel case rebuild each line from
+/// its words, which are split at anything but letters and digits and where
+/// the case changes, as in `parseHTTPRequest`.
+pub fn convert_case(text: &str, case: Case, locale: Locale) -> String {
+    let identifier = |line: &str| {
+        let words = words(line);
+        match case {
+            Case::Snake => words.iter().map(|word| locale.lower(word)).collect::<Vec<_>>().join("_"),
+            Case::Kebab => words.iter().map(|word| locale.lower(word)).collect::<Vec<_>>().join("-"),
+            _ => words
+                .iter()
+                .enumerate()
+                .map(|(idx, word)| if idx == 0 { locale.lower(word) } else { locale.capitalize(word) })
+                .collect(),
+        }
+    };
+    match case {
+        Case::Upper => locale.upper(text),
+        Case::Lower => locale.lower(text),
+        Case::Title => text.split_inclusive(char::is_whitespace).map(|word| locale.capitalize(word)).collect(),
+        Case::Snake | Case::Kebab | Case::Camel => text.split('\n').map(identifier).collect::<Vec<_>>().join("\n"),
+    }
+}
+
+/// Splits `text` into words for the identifier cases.
+fn words(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut prev: Option<char> = None;
+    for (idx, &(pos, c)) in chars.iter().enumerate() {
+        if is_combining_mark(c) && start.is_some() {
+            continue;
+        }
+        if !c.is_alphanumeric() {
+            if let Some(start) = start.take() {
+                words.push(&text[start..pos]);
+            }
+            continue;
+        }
+        if let (Some(from), Some(prev)) = (start, prev) {
+            let next = chars.get(idx + 1).map(|&(_, next)| next);
+            // fooBar and foo2Bar split before the B, HTTPServer before the S.
+            let boundary = c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next.is_some_and(char::is_lowercase)));
+            if boundary {
+                words.push(&text[from..pos]);
+                start = Some(pos);
+            }
+        }
+        start.get_or_insert(pos);
+        prev = Some(c);
+    }
+    if let Some(start) = start {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Puts `text` into the normalization `form`.
+pub fn normalize(text: &str, form: Normalization) -> String {
+    match form {
+        Normalization::Nfc => text.nfc().collect(),
+        Normalization::Nfd => text.nfd().collect(),
+        Normalization::Nfkc => text.nfkc().collect(),
+        Normalization::Nfkd => text.nfkd().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert_case, normalize, Case, Locale, Normalization};
+
+    fn check(case: Case, text: &str, expected: &str) {
+        assert_eq!(convert_case(text, case, Locale::Root), expected, "{case:?} of {text:?}");
+    }
+
+    #[test]
+    fn test_upper_lower() {
+        check(Case::Upper, "straße", "STRASSE");
+        check(Case::Lower, "ΟΔΟΣ İ", "οδος i\u{307}");
+        check(Case::Title, "hello  wORLD\tǆungla", "Hello  World\tǅungla");
+        check(Case::Title, "ßa", "Ssa");
+    }
+
+    #[test]
+    fn test_turkish() {
+        let tr = Locale::from_name("tr_TR.UTF-8");
+        assert_eq!(tr, Locale::Turkic);
+        assert_eq!(Locale::from_name("en_US"), Locale::Root);
+        assert_eq!(convert_case("istanbul", Case::Upper, tr), "İSTANBUL");
+        assert_eq!(convert_case("DİYARBAKIR", Case::Lower, tr), "diyarbakır");
+        assert_eq!(convert_case("ıi", Case::Title, tr), "Ii");
+        assert_eq!(convert_case("ıi", Case::Title, Locale::Root), "Ii");
+        assert_eq!(convert_case("iğne ipliği", Case::Camel, tr), "iğneİpliği");
+    }
+
+    #[test]
+    fn test_identifiers() {
+        check(Case::Snake, "Hello, World!", "hello_world");
+        check(Case::Snake, "parseHTTPRequest v2Api", "parse_http_request_v2_api");
+        check(Case::Kebab, "My Résumé.pdf", "my-résumé-pdf");
+        check(Case::Kebab, "Cafe\u{301} Menu", "cafe\u{301}-menu");
+        check(Case::Camel, "user_id\nSTRASSE name", "userId\nstrasseName");
+        check(Case::Snake, "--", "");
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Cafe\u{301}", Normalization::Nfc), "Café");
+        assert_eq!(normalize("Café", Normalization::Nfd), "Cafe\u{301}");
+        assert_eq!(normalize("ﬁ²", Normalization::Nfkc), "fi2");
+        assert_eq!(normalize("ﬁé", Normalization::Nfkd), "fie\u{301}");
+    }
+}
@@ -0,0 +1,213 @@
+use clap::ValueEnum;
+use cmdutil::{Error, Result};
+
+use crate::escape::unescape;
+
+/// A text encoding for `--encode` and `--decode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    /// RFC 4648 base64 with padding
+    Base64,
+    /// RFC 4648 URL-safe base64 without padding
+    Base64url,
+    /// Lowercase hexadecimal
+    Hex,
+    /// Percent-encoding of everything but unreserved URL characters
+    Url,
+    /// Printable ASCII with \n, \xHH, \uHHHH and \UHHHHHHHH escapes
+    UnicodeEscape,
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes `data` as `encoding`.
+pub fn encode(data: &[u8], encoding: Encoding) -> Vec<u8> {
+    match encoding {
+        Encoding::Base64 => base64_encode(data, BASE64, true),
+        Encoding::Base64url => base64_encode(data, BASE64URL, false),
+        Encoding::Hex => data.iter().flat_map(|b| format!("{b:02x}").into_bytes()).collect(),
+        Encoding::Url => url_encode(data),
+        Encoding::UnicodeEscape => unicode_escape(data),
+    }
+}
+
+/// Decodes `text` from `encoding`. Whitespace between base64 or hex digits
+/// is ignored. Text that is not valid in `encoding` is an
+/// [`Error::Invalid`], which like `base64 -d` exits with status 1.
+pub fn decode(text: &[u8], encoding: Encoding) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::Base64 | Encoding::Base64url => base64_decode(text),
+        Encoding::Hex => hex_decode(text),
+        Encoding::Url => url_decode(text),
+        Encoding::UnicodeEscape => {
+            let text = std::str::from_utf8(text).map_err(|_| invalid(encoding))?;
+            Ok(unescape(text).bytes)
+        }
+    }
+}
+
+fn invalid(encoding: Encoding) -> Error {
+    let name = encoding.to_possible_value().map_or(String::new(), |value| value.get_name().to_string());
+    Error::Invalid(format!("invalid {name} input"))
+}
+
+fn base64_encode(data: &[u8], alphabet: &[u8; 64], pad: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (idx, &b)| bits | (b as u32) << (16 - 8 * idx));
+        let digits = chunk.len() + 1;
+        for idx in 0..4 {
+            if idx < digits {
+                out.push(alphabet[(bits >> (18 - 6 * idx) & 0x3f) as usize]);
+            } else if pad {
+                out.push(b'=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes either base64 alphabet, with or without padding.
+fn base64_decode(text: &[u8]) -> Result<Vec<u8>> {
+    let digits: Vec<u8> = text.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    let unpadded = digits.strip_suffix(b"==").or_else(|| digits.strip_suffix(b"=")).unwrap_or(&digits);
+    let err = || invalid(Encoding::Base64);
+    if unpadded.len() % 4 == 1 || (unpadded.len() != digits.len() && !digits.len().is_multiple_of(4)) {
+        return Err(err());
+    }
+
+    let mut out = Vec::with_capacity(unpadded.len() * 3 / 4);
+    for chunk in unpadded.chunks(4) {
+        let mut bits = 0u32;
+        for (idx, &digit) in chunk.iter().enumerate() {
+            let value = match digit {
+                b'A'..=b'Z' => digit - b'A',
+                b'a'..=b'z' => digit - b'a' + 26,
+                b'0'..=b'9' => digit - b'0' + 52,
+                b'+' | b'-' => 62,
+                b'/' | b'_' => 63,
+                _ => return Err(err()),
+            };
+            bits |= (value as u32) << (18 - 6 * idx);
+        }
+        for idx in 0..chunk.len() - 1 {
+            out.push((bits >> (16 - 8 * idx)) as u8);
+        }
+    }
+    Ok(out)
+}
+
+fn hex_decode(text: &[u8]) -> Result<Vec<u8>> {
+    let digits: Vec<u8> = text.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(invalid(Encoding::Hex));
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| invalid(Encoding::Hex))
+        })
+        .collect()
+}
+
+fn url_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for &b in data {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            out.push(b);
+        } else {
+            out.extend_from_slice(format!("%{b:02X}").as_bytes());
+        }
+    }
+    out
+}
+
+fn url_decode(text: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        if b != b'%' {
+            out.push(b);
+            continue;
+        }
+        let byte = rest
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| invalid(Encoding::Url))?;
+        out.push(byte);
+        rest = &rest[2..];
+    }
+    Ok(out)
+}
+
+fn unicode_escape(data: &[u8]) -> Vec<u8> {
+    let mut out = String::with_capacity(data.len());
+    for chunk in data.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                ' '..='~' => out.push(c),
+                c if (c as u32) < 0x80 => out.push_str(&format!("\\x{:02x}", c as u32)),
+                c if (c as u32) <= 0xffff => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push_str(&format!("\\U{:08x}", c as u32)),
+            }
+        }
+        for b in chunk.invalid() {
+            out.push_str(&format!("\\x{b:02x}"));
+        }
+    }
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, Encoding};
+
+    fn round_trip(encoding: Encoding, data: &[u8], encoded: &str) {
+        assert_eq!(String::from_utf8(encode(data, encoding)).unwrap(), encoded);
+        assert_eq!(decode(encoded.as_bytes(), encoding).unwrap(), data);
+    }
+
+    #[test]
+    fn test_base64() {
+        round_trip(Encoding::Base64, b"", "");
+        round_trip(Encoding::Base64, b"f", "Zg==");
+        round_trip(Encoding::Base64, b"fo", "Zm8=");
+        round_trip(Encoding::Base64, b"foobar", "Zm9vYmFy");
+        round_trip(Encoding::Base64, b"\xfb\xff", "+/8=");
+        round_trip(Encoding::Base64url, b"\xfb\xff", "-_8");
+        assert_eq!(decode(b"Zm9v\nYmE=\n", Encoding::Base64).unwrap(), b"fooba");
+        assert!(decode(b"Zm9v!", Encoding::Base64).is_err());
+        assert!(decode(b"Z", Encoding::Base64).is_err());
+    }
+
+    #[test]
+    fn test_hex() {
+        round_trip(Encoding::Hex, b"Hi\xff", "4869ff");
+        assert_eq!(decode(b"48 69 FF", Encoding::Hex).unwrap(), b"Hi\xff");
+        let err = decode(b"486", Encoding::Hex).unwrap_err();
+        assert_eq!(err.to_string(), "invalid hex input");
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_url() {
+        round_trip(Encoding::Url, "a b/c?d=é~".as_bytes(), "a%20b%2Fc%3Fd%3D%C3%A9~");
+        assert!(decode(b"100%", Encoding::Url).is_err());
+    }
+
+    #[test]
+    fn test_unicode_escape() {
+        round_trip(Encoding::UnicodeEscape, "tab\t\\ é😀\x01".as_bytes(), r"tab\t\\ \u00e9\U0001f600\x01");
+        assert_eq!(encode(b"\xff", Encoding::UnicodeEscape), br"\xff");
+    }
+}
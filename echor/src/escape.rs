@@ -0,0 +1,138 @@
+/// The bytes produced by interpreting backslash escapes in one argument.
+#[derive(Debug, PartialEq)]
+pub struct Unescaped {
+    pub bytes: Vec<u8>,
+    /// A `\c` was found: nothing more should be written, not even the newline.
+    pub stop: bool,
+}
+
+/// Interprets GNU `echo -e` escapes: `\\ \a \b \c \e \f \n \r \t \v`,
+/// `\0NNN` and `\NNN` octal bytes, `\xHH` hex bytes, and `\uHHHH` /
+/// `\UHHHHHHHH` code points written as UTF-8. Unknown or malformed escapes
+/// are kept literally, backslash included.
+pub fn unescape(text: &str) -> Unescaped {
+    unescape_with(text, 3)
+}
+
+/// Interprets the escapes of a printf(1) format, which are those of
+/// [`unescape`] except that `\NNN` is one to three octal digits even when
+/// the first is `0`: `\101` is `A` and `\0101` is a backspace and `1`.
+pub fn unescape_format(text: &str) -> Unescaped {
+    unescape_with(text, 2)
+}
+
+/// Does the work of [`unescape`], with `\0` taking up to `zero_digits` more
+/// octal digits.
+fn unescape_with(text: &str, zero_digits: usize) -> Unescaped {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' || rest.is_empty() {
+            bytes.push(byte);
+            continue;
+        }
+
+        let escape = rest[0];
+        rest = &rest[1..];
+        match escape {
+            b'\\' => bytes.push(b'\\'),
+            b'a' => bytes.push(0x07),
+            b'b' => bytes.push(0x08),
+            b'c' => return Unescaped { bytes, stop: true },
+            b'e' => bytes.push(0x1b),
+            b'f' => bytes.push(0x0c),
+            b'n' => bytes.push(b'\n'),
+            b'r' => bytes.push(b'\r'),
+            b't' => bytes.push(b'\t'),
+            b'v' => bytes.push(0x0b),
+            b'0'..=b'7' => {
+                // `\1`..`\7` take up to two more digits.
+                let (first, max_digits) = if escape == b'0' { (0, zero_digits) } else { (escape - b'0', 2) };
+                let (value, used) = parse_digits(rest, 8, max_digits);
+                let value = (first as u32) * 8u32.pow(used as u32) + value;
+                bytes.push(value as u8);
+                rest = &rest[used..];
+            }
+            b'x' | b'u' | b'U' => {
+                let max_digits = match escape {
+                    b'x' => 2,
+                    b'u' => 4,
+                    _ => 8,
+                };
+                let (value, used) = parse_digits(rest, 16, max_digits);
+                if used == 0 {
+                    bytes.extend_from_slice(&[b'\\', escape]);
+                    continue;
+                }
+                rest = &rest[used..];
+                if escape == b'x' {
+                    bytes.push(value as u8);
+                } else {
+                    let c = char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER);
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+            }
+            _ => bytes.extend_from_slice(&[b'\\', escape]),
+        }
+    }
+
+    Unescaped { bytes, stop: false }
+}
+
+/// Parses up to `max_digits` leading digits of `text` in `radix`, returning
+/// the value and the number of digits used.
+fn parse_digits(text: &[u8], radix: u32, max_digits: usize) -> (u32, usize) {
+    let mut value = 0;
+    let mut used = 0;
+    for &byte in text.iter().take(max_digits) {
+        match (byte as char).to_digit(radix) {
+            Some(digit) => {
+                value = value * radix + digit;
+                used += 1;
+            }
+            None => break,
+        }
+    }
+    (value, used)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unescape, unescape_format, Unescaped};
+
+    fn bytes(text: &str) -> Vec<u8> {
+        unescape(text).bytes
+    }
+
+    #[test]
+    fn test_simple_escapes() {
+        assert_eq!(bytes(r"a\tb\n"), b"a\tb\n");
+        assert_eq!(bytes(r"\\ \a\b\e\f\r\v"), b"\\ \x07\x08\x1b\x0c\r\x0b");
+    }
+
+    #[test]
+    fn test_numeric_escapes() {
+        assert_eq!(bytes(r"\0101\101\0\x41\x4g\xff"), b"AA\0A\x04g\xff");
+        assert_eq!(bytes(r"é\U0001F600"), "é😀".as_bytes());
+        assert_eq!(bytes(r"\uD800"), "\u{fffd}".as_bytes());
+        assert_eq!(unescape_format(r"\101\0101\0\1234").bytes, b"A\x081\0S4");
+    }
+
+    #[test]
+    fn test_literal_escapes() {
+        assert_eq!(bytes(r"\q\xZ\u\"), br"\q\xZ\u\");
+    }
+
+    #[test]
+    fn test_stop() {
+        assert_eq!(
+            unescape(r"ab\cde"),
+            Unescaped {
+                bytes: b"ab".to_vec(),
+                stop: true
+            }
+        );
+    }
+}
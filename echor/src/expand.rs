@@ -0,0 +1,165 @@
+use cmdutil::{Error, Result};
+
+/// Substitutes variables in `text` the way a POSIX shell does inside double
+/// quotes, looking values up with `lookup`.
+///
+/// Supports `$VAR`, `${VAR}`, `${VAR-default}`, `${VAR:-default}`,
+/// `${VAR?message}` and `${VAR:?message}`; with the colon an empty value
+/// counts as unset. Defaults are expanded in turn. An unset variable with no
+/// default expands to nothing, or is an error when `strict` is set. A `$`
+/// that does not start a variable is kept as is.
+///
+/// A malformed `${...}` is a usage error; a variable that `?` or `strict`
+/// requires but is not set is an [`Error::Invalid`], as the text itself is
+/// fine.
+pub fn expand<F: Fn(&str) -> Option<String>>(text: &str, lookup: &F, strict: bool) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(at) = rest.find('$') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+
+        if let Some(braced) = rest.strip_prefix('{') {
+            let end = closing_brace(braced).ok_or_else(|| Error::Usage(format!("${{{braced}: bad substitution")))?;
+            out.push_str(&expand_braced(&braced[..end], lookup, strict)?);
+            rest = &braced[end + 1..];
+            continue;
+        }
+
+        let len = name_len(rest);
+        if len == 0 {
+            out.push('$');
+            continue;
+        }
+        let name = &rest[..len];
+        match lookup(name) {
+            Some(value) => out.push_str(&value),
+            None if strict => return Err(unbound(name)),
+            None => {}
+        }
+        rest = &rest[len..];
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+/// Expands the inside of `${...}`.
+fn expand_braced<F: Fn(&str) -> Option<String>>(inner: &str, lookup: &F, strict: bool) -> Result<String> {
+    let len = name_len(inner);
+    let (name, operator) = inner.split_at(len);
+    let bad = || Error::Usage(format!("${{{inner}}}: bad substitution"));
+    if name.is_empty() {
+        return Err(bad());
+    }
+
+    let (colon, operator) = match operator.strip_prefix(':') {
+        Some(operator) => (true, operator),
+        None => (false, operator),
+    };
+    let value = lookup(name).filter(|value| !(colon && value.is_empty()));
+
+    match operator.chars().next() {
+        None if !colon => match value {
+            Some(value) => Ok(value),
+            None if strict => Err(unbound(name)),
+            None => Ok(String::new()),
+        },
+        Some('-') => match value {
+            Some(value) => Ok(value),
+            None => expand(&operator[1..], lookup, strict),
+        },
+        Some('?') => match value {
+            Some(value) => Ok(value),
+            None if operator.len() == 1 => Err(unset(format!("{name}: parameter null or not set"))),
+            None => Err(unset(format!("{name}: {}", expand(&operator[1..], lookup, strict)?))),
+        },
+        _ => Err(bad()),
+    }
+}
+
+fn unbound(name: &str) -> Error {
+    unset(format!("{name}: unbound variable"))
+}
+
+/// The error for a variable that had to be set but was not.
+fn unset(message: String) -> Error {
+    Error::Invalid(message)
+}
+
+/// The length of the variable name at the start of `text`, or 0 if there
+/// is none.
+fn name_len(text: &str) -> usize {
+    if !text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return 0;
+    }
+    text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(text.len())
+}
+
+/// Finds the `}` closing a `${`, skipping over nested `${...}` in defaults.
+fn closing_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut prev = None;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '{' if prev == Some('$') => depth += 1,
+            '}' if depth == 0 => return Some(idx),
+            '}' => depth -= 1,
+            _ => {}
+        }
+        prev = Some(c);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "APP" => Some("api".to_string()),
+            "ENV" => Some("prod".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn check(text: &str, expected: &str) {
+        assert_eq!(expand(text, &lookup, false).unwrap(), expected, "expanding {text:?}");
+    }
+
+    #[test]
+    fn test_expand_variables() {
+        check("Deploying $APP to ${ENV}!", "Deploying api to prod!");
+        check("$APP_x ${APP}_x $MISSING.", " api_x .");
+        check("cost: $5 and $ alone$", "cost: $5 and $ alone$");
+    }
+
+    #[test]
+    fn test_expand_defaults() {
+        check("${MISSING:-web} ${EMPTY:-web} ${EMPTY-web}", "web web ");
+        check("${APP:-web} ${MISSING:-${ENV:-dev}-1}", "api prod-1");
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let err = expand("${MISSING:?is required}", &lookup, false).unwrap_err();
+        assert_eq!(err.to_string(), "MISSING: is required");
+        assert_eq!(err.exit_code(), 1);
+        let err = expand("${EMPTY:?}", &lookup, false).unwrap_err();
+        assert_eq!(err.to_string(), "EMPTY: parameter null or not set");
+        assert_eq!(expand("${APP", &lookup, false).unwrap_err().exit_code(), 2);
+        assert_eq!(expand("${APP/x}", &lookup, false).unwrap_err().exit_code(), 2);
+    }
+
+    #[test]
+    fn test_expand_strict() {
+        assert_eq!(expand("$APP ${MISSING:-x}", &lookup, true).unwrap(), "api x");
+        let err = expand("$MISSING", &lookup, true).unwrap_err();
+        assert_eq!(err.to_string(), "MISSING: unbound variable");
+        assert_eq!(err.exit_code(), 1);
+        assert!(expand("${MISSING}", &lookup, true).is_err());
+    }
+}
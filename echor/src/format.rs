@@ -0,0 +1,515 @@
+use cmdutil::{Error, Result};
+
+use crate::{
+    escape::{unescape, unescape_format},
+    quote::{quote, Quoting},
+};
+
+/// One parsed piece of a format string.
+#[derive(Debug)]
+enum Piece {
+    Literal(Vec<u8>),
+    /// A `\c` in the format: stop all output.
+    Stop,
+    Spec(Spec),
+}
+
+/// The largest width or precision accepted, so that a typo such as
+/// `%99999999d` fails instead of trying to allocate the padding. It is also
+/// as much as Rust's own formatting machinery takes.
+const MAX_COUNT: usize = u16::MAX as usize;
+
+#[derive(Debug, Clone, Copy)]
+enum Count {
+    Fixed(usize),
+    /// `*`: taken from the next argument.
+    Arg,
+}
+
+#[derive(Debug, Default)]
+struct Flags {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alt: bool,
+    zero: bool,
+}
+
+#[derive(Debug)]
+struct Spec {
+    flags: Flags,
+    width: Option<Count>,
+    precision: Option<Count>,
+    conversion: char,
+}
+
+/// What [`printf`] made of its arguments.
+#[derive(Debug, PartialEq)]
+pub struct Printed {
+    pub bytes: Vec<u8>,
+    /// A message for each argument that should have been a number but was
+    /// not. Each was formatted as zero.
+    pub errors: Vec<String>,
+}
+
+/// Formats `args` the way POSIX `printf FORMAT ARGS...` does.
+///
+/// Supports the flags `-+ #0`, width and precision (either may be `*`), and
+/// the conversions `%s %b %q %c %d %i %u %o %x %X %f %F %e %E %g %G %%`.
+/// Backslash escapes in `format` are interpreted by [`unescape_format`] and
+/// those in `%b` arguments as by `echo -e`. The format is applied again while
+/// arguments remain; missing arguments read as empty strings or zero.
+///
+/// Only a malformed format, or a width or precision too large to honour, is
+/// an error. Like printf(1), an argument that is not a valid number is
+/// formatted as zero and listed in [`Printed::errors`].
+pub fn printf<S: AsRef<str>>(format: &str, args: &[S]) -> Result<Printed> {
+    let pieces = parse(format)?;
+    let mut args = Args { args, next: 0, errors: Vec::new() };
+    let mut out = Vec::new();
+
+    loop {
+        let start = args.next;
+        for piece in &pieces {
+            let stop = match piece {
+                Piece::Literal(bytes) => {
+                    out.extend_from_slice(bytes);
+                    false
+                }
+                Piece::Stop => true,
+                Piece::Spec(spec) => format_spec(spec, &mut args, &mut out)?,
+            };
+            if stop {
+                return Ok(Printed { bytes: out, errors: args.errors });
+            }
+        }
+        if args.next == start || args.next >= args.args.len() {
+            return Ok(Printed { bytes: out, errors: args.errors });
+        }
+    }
+}
+
+struct Args<'a, S> {
+    args: &'a [S],
+    next: usize,
+    errors: Vec<String>,
+}
+
+impl<'a, S: AsRef<str>> Args<'a, S> {
+    fn next(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.next)?;
+        self.next += 1;
+        Some(arg.as_ref())
+    }
+
+    /// Parses `arg` as an integer, or notes that it isn't one and reads it
+    /// as zero.
+    fn int(&mut self, arg: &str) -> i128 {
+        parse_int(arg).unwrap_or_else(|| {
+            self.errors.push(format!("'{arg}': expected a numeric value"));
+            0
+        })
+    }
+
+    /// Parses `arg` as a floating point number, or as zero like [`Args::int`].
+    fn float(&mut self, arg: &str) -> f64 {
+        parse_float(arg).unwrap_or_else(|| {
+            self.errors.push(format!("'{arg}': expected a numeric value"));
+            0.0
+        })
+    }
+
+    fn count(&mut self, count: Option<Count>, name: &str) -> Result<Option<i64>> {
+        match count {
+            None => Ok(None),
+            Some(Count::Fixed(n)) => Ok(Some(n as i64)),
+            Some(Count::Arg) => {
+                let arg = self.next().unwrap_or("");
+                let n = self.int(arg);
+                if n.unsigned_abs() > MAX_COUNT as u128 {
+                    return Err(Error::Invalid(format!("'{arg}': invalid {name}")));
+                }
+                Ok(Some(n as i64))
+            }
+        }
+    }
+}
+
+fn parse(format: &str) -> Result<Vec<Piece>> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut chars = format.chars().peekable();
+
+    let flush = |literal: &mut String, pieces: &mut Vec<Piece>| {
+        if !literal.is_empty() {
+            let unescaped = unescape_format(literal);
+            pieces.push(Piece::Literal(unescaped.bytes));
+            if unescaped.stop {
+                pieces.push(Piece::Stop);
+            }
+            literal.clear();
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            literal.push(c);
+            continue;
+        }
+        flush(&mut literal, &mut pieces);
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            pieces.push(Piece::Literal(b"%".to_vec()));
+            continue;
+        }
+
+        let mut flags = Flags::default();
+        while let Some(&c) = chars.peek() {
+            match c {
+                '-' => flags.left = true,
+                '+' => flags.plus = true,
+                ' ' => flags.space = true,
+                '#' => flags.alt = true,
+                '0' => flags.zero = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        let width = parse_count(&mut chars, "field width")?;
+        let precision = if chars.peek() == Some(&'.') {
+            chars.next();
+            Some(parse_count(&mut chars, "precision")?.unwrap_or(Count::Fixed(0)))
+        } else {
+            None
+        };
+        match chars.next() {
+            Some(conversion) if "sbqcdiuoxXfFeEgG".contains(conversion) => {
+                pieces.push(Piece::Spec(Spec { flags, width, precision, conversion }));
+            }
+            Some(conversion) => {
+                return Err(Error::Usage(format!("%{conversion}: invalid conversion specification")));
+            }
+            None => return Err(Error::Usage("%: missing conversion specification".to_string())),
+        }
+    }
+    flush(&mut literal, &mut pieces);
+    Ok(pieces)
+}
+
+/// Parses a width or precision, `name`, which may be at most [`MAX_COUNT`].
+fn parse_count(chars: &mut std::iter::Peekable<std::str::Chars>, name: &str) -> Result<Option<Count>> {
+    if chars.peek() == Some(&'*') {
+        chars.next();
+        return Ok(Some(Count::Arg));
+    }
+    let mut digits = String::new();
+    while let Some(&digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+        digits.push(digit);
+        chars.next();
+    }
+    if digits.is_empty() {
+        return Ok(None);
+    }
+    match digits.parse() {
+        Ok(count) if count <= MAX_COUNT => Ok(Some(Count::Fixed(count))),
+        _ => Err(Error::Usage(format!("{digits}: invalid {name}"))),
+    }
+}
+
+/// Formats one directive into `out`, returning whether output should stop.
+fn format_spec<S: AsRef<str>>(spec: &Spec, args: &mut Args<S>, out: &mut Vec<u8>) -> Result<bool> {
+    let mut left = spec.flags.left;
+    let width = match args.count(spec.width, "field width")? {
+        Some(width) if width < 0 => {
+            left = true;
+            width.unsigned_abs() as usize
+        }
+        width => width.unwrap_or(0) as usize,
+    };
+    // A negative precision from `*` counts as no precision at all.
+    let precision = args.count(spec.precision, "precision")?.and_then(|p| usize::try_from(p).ok());
+    let arg = args.next().unwrap_or("");
+
+    let pad = |body: &[u8], out: &mut Vec<u8>| {
+        let fill = width.saturating_sub(body.len());
+        if !left {
+            out.resize(out.len() + fill, b' ');
+        }
+        out.extend_from_slice(body);
+        if left {
+            out.resize(out.len() + fill, b' ');
+        }
+    };
+
+    match spec.conversion {
+        's' => {
+            let text = match precision {
+                Some(precision) => arg.chars().take(precision).collect(),
+                None => arg.to_string(),
+            };
+            pad(text.as_bytes(), out);
+        }
+        'b' => {
+            let mut unescaped = unescape(arg);
+            if let Some(precision) = precision {
+                unescaped.bytes.truncate(precision);
+            }
+            pad(&unescaped.bytes, out);
+            return Ok(unescaped.stop);
+        }
+        'q' => pad(&quote(arg.as_bytes(), Quoting::Shell), out),
+        'c' => pad(arg.chars().next().map(String::from).unwrap_or_default().as_bytes(), out),
+        conversion => {
+            let number = match conversion {
+                'd' | 'i' => format_signed(args.int(arg), precision, &spec.flags),
+                'u' | 'o' | 'x' | 'X' => format_unsigned(args.int(arg), conversion, precision, &spec.flags),
+                _ => format_float(args.float(arg), conversion, precision, &spec.flags),
+            };
+            let zero_pad = spec.flags.zero && !left && (precision.is_none() || "fFeEgG".contains(conversion)) && number.finite;
+            if zero_pad {
+                let fill = width.saturating_sub(number.prefix.len() + number.digits.len());
+                out.extend_from_slice(number.prefix.as_bytes());
+                out.resize(out.len() + fill, b'0');
+                out.extend_from_slice(number.digits.as_bytes());
+            } else {
+                pad(format!("{}{}", number.prefix, number.digits).as_bytes(), out);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// A formatted number split where zero padding goes.
+struct Number {
+    /// Sign and radix prefix such as `-` or `0x`.
+    prefix: String,
+    digits: String,
+    finite: bool,
+}
+
+fn sign(negative: bool, flags: &Flags) -> &'static str {
+    match (negative, flags.plus, flags.space) {
+        (true, _, _) => "-",
+        (false, true, _) => "+",
+        (false, false, true) => " ",
+        _ => "",
+    }
+}
+
+fn with_precision(digits: String, precision: Option<usize>) -> String {
+    match precision {
+        Some(0) if digits == "0" => String::new(),
+        Some(precision) if digits.len() < precision => format!("{digits:0>precision$}"),
+        _ => digits,
+    }
+}
+
+fn format_signed(value: i128, precision: Option<usize>, flags: &Flags) -> Number {
+    Number {
+        prefix: sign(value < 0, flags).to_string(),
+        digits: with_precision(value.unsigned_abs().to_string(), precision),
+        finite: true,
+    }
+}
+
+fn format_unsigned(value: i128, conversion: char, precision: Option<usize>, flags: &Flags) -> Number {
+    // Negative values wrap around the way C's conversion to unsigned does.
+    let value = if value < 0 { value as i64 as u64 } else { value as u64 };
+    let digits = match conversion {
+        'o' => format!("{value:o}"),
+        'x' => format!("{value:x}"),
+        'X' => format!("{value:X}"),
+        _ => value.to_string(),
+    };
+    let mut digits = with_precision(digits, precision);
+    let mut prefix = String::new();
+    if flags.alt {
+        match conversion {
+            'o' if !digits.starts_with('0') => digits.insert(0, '0'),
+            'x' if value != 0 => prefix.push_str("0x"),
+            'X' if value != 0 => prefix.push_str("0X"),
+            _ => {}
+        }
+    }
+    Number { prefix, digits, finite: true }
+}
+
+fn format_float(value: f64, conversion: char, precision: Option<usize>, flags: &Flags) -> Number {
+    let prefix = sign(value.is_sign_negative() && !value.is_nan(), flags).to_string();
+    let upper = conversion.is_ascii_uppercase();
+    let abs = value.abs();
+
+    if !abs.is_finite() {
+        let digits = if abs.is_nan() { "nan" } else { "inf" };
+        let digits = if upper { digits.to_uppercase() } else { digits.to_string() };
+        return Number { prefix, digits, finite: false };
+    }
+
+    let precision = precision.unwrap_or(6);
+    let mut digits = match conversion.to_ascii_lowercase() {
+        'f' => format!("{abs:.precision$}"),
+        'e' => format_exp(abs, precision),
+        _ => {
+            let precision = precision.max(1);
+            let exponent = exponent_of(&format_exp(abs, precision - 1));
+            let mut digits = if exponent < -4 || exponent >= precision as i32 {
+                format_exp(abs, precision - 1)
+            } else {
+                format!("{abs:.*}", (precision as i32 - 1 - exponent) as usize)
+            };
+            if !flags.alt {
+                strip_trailing_zeros(&mut digits);
+            }
+            digits
+        }
+    };
+    if flags.alt && !digits.contains('.') {
+        let at = digits.find('e').unwrap_or(digits.len());
+        digits.insert(at, '.');
+    }
+    if upper {
+        digits = digits.to_uppercase();
+    }
+    Number { prefix, digits, finite: true }
+}
+
+/// Formats like C's `%.*e`: `1.500000e+00` rather than Rust's `1.5e0`.
+fn format_exp(value: f64, precision: usize) -> String {
+    let formatted = format!("{value:.precision$e}");
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{mantissa}e{sign}{:02}", exponent.abs())
+}
+
+fn exponent_of(formatted: &str) -> i32 {
+    formatted.split_once('e').and_then(|(_, exponent)| exponent.parse().ok()).unwrap_or(0)
+}
+
+fn strip_trailing_zeros(digits: &mut String) {
+    let exponent_at = digits.find('e').unwrap_or(digits.len());
+    let (mantissa, exponent) = digits.split_at(exponent_at);
+    if mantissa.contains('.') {
+        let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+        *digits = format!("{mantissa}{exponent}");
+    }
+}
+
+/// Parses an integer argument: decimal, `0x` hex, `0` octal, or `'c` for
+/// the code point of `c`.
+fn parse_int(arg: &str) -> Option<i128> {
+    let text = arg.trim_start();
+    if let Some(quoted) = text.strip_prefix(['\'', '"']) {
+        return Some(quoted.chars().next().map_or(0, |c| c as i128));
+    }
+    if text.is_empty() {
+        return Some(0);
+    }
+
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let parsed = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8)
+    } else {
+        digits.parse()
+    };
+    let value = parsed.ok()? as i128;
+    Some(if negative { -value } else { value })
+}
+
+fn parse_float(arg: &str) -> Option<f64> {
+    let text = arg.trim_start();
+    if text.starts_with(['\'', '"']) {
+        return parse_int(arg).map(|value| value as f64);
+    }
+    if text.is_empty() {
+        return Some(0.0);
+    }
+    text.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::printf;
+
+    fn check(format: &str, args: &[&str], expected: &str) {
+        let printed = printf(format, args).unwrap();
+        assert_eq!(printed.errors, [] as [String; 0], "format {format:?} with {args:?}");
+        assert_eq!(String::from_utf8(printed.bytes).unwrap(), expected, "format {format:?} with {args:?}");
+    }
+
+    #[test]
+    fn test_printf_strings() {
+        check(r"%-10s|%5s|%.2s\n", &["name", "ab", "xyz"], "name      |   ab|xy\n");
+        check("%c%c", &["hello", ""], "h");
+        check("100%% %s", &["done"], "100% done");
+        check("%q %q %q", &["plain", "it's", ""], r"plain 'it'\''s' ''");
+        check(r"%b|%s", &[r"a\tb", r"a\tb"], "a\tb|a\\tb");
+    }
+
+    #[test]
+    fn test_printf_integers() {
+        check("%5d|%-4i|%05d|%+d|% d", &["42", "7", "-42", "3", "3"], "   42|7   |-0042|+3| 3");
+        check("%x %X %#x %o %#o %u", &["255", "255", "255", "8", "8", "-1"], "ff FF 0xff 10 010 18446744073709551615");
+        check("%d %d %d %d", &["0x10", "010", "'A", ""], "16 8 65 0");
+        check("%.3d|%*d|%-*d|", &["5", "4", "1", "3", "2"], "005|   1|2  |");
+    }
+
+    #[test]
+    fn test_printf_floats() {
+        check("%.2f|%f|%8.3f|%08.2f", &["3.14159", "1", "-2.5", "3.14159"], "3.14|1.000000|  -2.500|00003.14");
+        check("%e|%.2E|%e", &["12345.678", "0.000123", "0"], "1.234568e+04|1.23E-04|0.000000e+00");
+        check("%g|%g|%g|%g|%G", &["100000", "1000000", "0.0001", "0.00001", "1.5e-10"], "100000|1e+06|0.0001|1e-05|1.5E-10");
+        check("%#g|%.3g|%g", &["1", "3.14159", "inf"], "1.00000|3.14|inf");
+    }
+
+    #[test]
+    fn test_printf_reuse() {
+        check(r"%s=%d\n", &["a", "1", "b"], "a=1\nb=0\n");
+        check(r"no directives\n", &["ignored"], "no directives\n");
+        check(r"%s\c%s", &["a", "b", "c"], "a");
+        check(r"%b-", &[r"x\cy", "z"], "x");
+    }
+
+    #[test]
+    fn test_printf_escapes() {
+        check(r"\101\0101|%b", &[r"\0101\101"], "A\x081|AA");
+        check(r"\x41\u00e9\t\\", &[], "Aé\t\\");
+    }
+
+    #[test]
+    fn test_printf_bad_numbers() {
+        let printed = printf(r"%d|%.1f|%*d|%s\n", &["abc", "1.5x", "-", "7", "ok"]).unwrap();
+        assert_eq!(printed.bytes, b"0|0.0|7|ok\n");
+        assert_eq!(
+            printed.errors,
+            ["'abc': expected a numeric value", "'1.5x': expected a numeric value", "'-': expected a numeric value"]
+        );
+    }
+
+    #[test]
+    fn test_printf_errors() {
+        assert_eq!(printf("%z", &["abc"]).unwrap_err().exit_code(), 2);
+        assert!(printf("abc%", &[] as &[&str]).is_err());
+    }
+
+    #[test]
+    fn test_printf_huge_counts() {
+        let err = printf("%99999999999999999999d", &["1"]).unwrap_err();
+        assert_eq!(err.to_string(), "99999999999999999999: invalid field width");
+        assert_eq!(err.exit_code(), 2);
+        let err = printf("%.65536f", &["1"]).unwrap_err();
+        assert_eq!(err.to_string(), "65536: invalid precision");
+        let err = printf("%*d", &["-99999999999", "1"]).unwrap_err();
+        assert_eq!(err.to_string(), "'-99999999999': invalid field width");
+        assert_eq!(err.exit_code(), 1);
+        let err = printf("%.*f", &["4294967296", "1"]).unwrap_err();
+        assert_eq!(err.to_string(), "'4294967296': invalid precision");
+        let zeros = "0".repeat(65534);
+        check("%*d|%.*d", &["65535", "1", "65535", "1"], &format!("{:>65535}|{zeros}1", 1));
+    }
+}
@@ -0,0 +1,297 @@
+use clap::ValueEnum;
+use unicode_width::UnicodeWidthChar;
+
+/// How [`layout`] places each line within the width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretch wrapped lines to the full width; the last line of each
+    /// paragraph stays left-aligned
+    Justify,
+}
+
+/// The characters [`layout`] draws a box with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Frame {
+    Unicode,
+    Ascii,
+}
+
+impl Frame {
+    /// Corners clockwise from top left, then the horizontal and vertical
+    /// edges.
+    fn chars(self) -> [char; 6] {
+        match self {
+            Frame::Unicode => ['┌', '┐', '┘', '└', '─', '│'],
+            Frame::Ascii => ['+', '+', '+', '+', '-', '|'],
+        }
+    }
+}
+
+/// Options for [`layout`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    /// Total width in terminal columns, box included.
+    pub width: Option<usize>,
+    pub wrap: bool,
+    pub align: Align,
+    pub frame: Option<Frame>,
+}
+
+/// The width [`layout`] wraps to when none is given.
+pub const DEFAULT_WIDTH: usize = 80;
+
+impl Layout {
+    /// Whether [`layout`] would change anything.
+    pub fn is_active(&self) -> bool {
+        self.wrap || self.frame.is_some() || (self.width.is_some() && self.align != Align::Left)
+    }
+}
+
+/// Lays out the lines of `text` by display width, so wide CJK characters
+/// and emoji count as two columns, ANSI color codes as none and bytes that
+/// are not UTF-8 as one each; those bytes are kept as they are.
+///
+/// Each laid-out line is passed through `paint` before it is padded and
+/// boxed, so a style drawn by `paint` never runs across a line break or
+/// over the box. Color codes already in `text` are closed at the end of
+/// each line and opened again at the start of the next for the same reason.
+pub fn layout<F: Fn(&[u8]) -> Vec<u8>>(text: &[u8], layout: &Layout, paint: F) -> Vec<u8> {
+    let border = if layout.frame.is_some() { 4 } else { 0 };
+    let target = layout.width.map(|width| width.saturating_sub(border).max(1));
+
+    // Each line, and whether it ends a paragraph.
+    let mut lines: Vec<(Vec<u8>, bool)> = Vec::new();
+    for paragraph in text.split(|&b| b == b'\n') {
+        if layout.wrap {
+            let wrapped = wrap(paragraph, target.unwrap_or(DEFAULT_WIDTH - border));
+            let count = wrapped.len();
+            lines.extend(wrapped.into_iter().enumerate().map(|(idx, line)| (line, idx + 1 == count)));
+        } else {
+            lines.push((paragraph.to_vec(), true));
+        }
+    }
+    reopen_styles(&mut lines);
+
+    let inner = target.unwrap_or_else(|| lines.iter().map(|(line, _)| width(line)).max().unwrap_or(0));
+    let padded = layout.frame.is_some();
+    let mut lines: Vec<Vec<u8>> = lines
+        .into_iter()
+        .map(|(line, last)| align(&line, inner, layout.align, last, padded, &paint))
+        .collect();
+
+    if let Some(frame) = layout.frame {
+        let [top_left, top_right, bottom_right, bottom_left, horizontal, vertical] = frame.chars();
+        // Unwrapped lines may be wider than asked for; the box grows to fit.
+        let inner = lines.iter().map(|line| width(line)).max().unwrap_or(0).max(inner);
+        let rule = horizontal.to_string().repeat(inner + 2);
+        lines = lines
+            .into_iter()
+            .map(|line| {
+                let fill = " ".repeat(inner - width(&line));
+                [format!("{vertical} ").as_bytes(), &line, format!("{fill} {vertical}").as_bytes()].concat()
+            })
+            .collect();
+        lines.insert(0, format!("{top_left}{rule}{top_right}").into_bytes());
+        lines.push(format!("{bottom_left}{rule}{bottom_right}").into_bytes());
+    }
+    lines.join(&b'\n')
+}
+
+/// Ends each line that leaves an SGR color sequence in effect with a reset,
+/// and starts the next line with the sequences that were in effect.
+fn reopen_styles(lines: &mut [(Vec<u8>, bool)]) {
+    let mut open: Vec<u8> = Vec::new();
+    for (line, _) in lines {
+        let mut styled = open.clone();
+        for (unit, _) in units(line) {
+            if unit.starts_with(b"\x1b[") && unit.ends_with(b"m") {
+                if unit == RESET || unit == b"\x1b[m" {
+                    open.clear();
+                } else {
+                    open.extend_from_slice(unit);
+                }
+            }
+        }
+        styled.extend_from_slice(line);
+        if !open.is_empty() {
+            styled.extend_from_slice(RESET);
+        }
+        *line = styled;
+    }
+}
+
+const RESET: &[u8] = b"\x1b[0m";
+
+/// Places `line`, painted, within `inner` columns. Trailing space is only
+/// added when `padded`, i.e. when a box edge follows.
+fn align<F>(line: &[u8], inner: usize, align: Align, last: bool, padded: bool, paint: F) -> Vec<u8>
+where
+    F: Fn(&[u8]) -> Vec<u8>,
+{
+    let fill = inner.saturating_sub(width(line));
+    let (left, right) = match align {
+        Align::Left => (0, fill),
+        Align::Center => (fill / 2, fill - fill / 2),
+        Align::Right => (fill, 0),
+        Align::Justify if last => (0, fill),
+        Align::Justify => return paint(&justify(line, fill)),
+    };
+    let right = if padded { right } else { 0 };
+    [" ".repeat(left).as_bytes(), &paint(line), " ".repeat(right).as_bytes()].concat()
+}
+
+/// Widens the gaps between the words of `line` by `fill` spaces in total,
+/// leftmost gaps first.
+fn justify(line: &[u8], fill: usize) -> Vec<u8> {
+    let words: Vec<&[u8]> = line.split(|&b| b == b' ').filter(|word| !word.is_empty()).collect();
+    let gaps = words.len().saturating_sub(1);
+    if gaps == 0 {
+        return line.to_vec();
+    }
+    let mut out = Vec::new();
+    for (idx, word) in words.iter().enumerate() {
+        if idx > 0 {
+            let extra = fill / gaps + usize::from(idx <= fill % gaps);
+            out.resize(out.len() + 1 + extra, b' ');
+        }
+        out.extend_from_slice(word);
+    }
+    out
+}
+
+/// Greedily fills lines of at most `max` columns with the words of
+/// `paragraph`, breaking words that are wider than a whole line.
+fn wrap(paragraph: &[u8], max: usize) -> Vec<Vec<u8>> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let mut line_width = 0;
+
+    for word in paragraph.split(|&b| b == b' ').filter(|word| !word.is_empty()) {
+        let word_width = width(word);
+        if line_width > 0 && line_width + 1 + word_width > max {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+        }
+        if word_width > max {
+            for (unit, unit_width) in units(word) {
+                if line_width > 0 && line_width + unit_width > max {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+                line.extend_from_slice(unit);
+                line_width += unit_width;
+            }
+            continue;
+        }
+        if line_width > 0 {
+            line.push(b' ');
+            line_width += 1;
+        }
+        line.extend_from_slice(word);
+        line_width += word_width;
+    }
+    lines.push(line);
+    lines
+}
+
+/// The display width of `text` in terminal columns.
+pub fn width(text: &[u8]) -> usize {
+    units(text).map(|(_, width)| width).sum()
+}
+
+/// Splits `text` into characters, ANSI escape sequences and bytes that are
+/// not UTF-8, with the columns each takes up.
+fn units(text: &[u8]) -> impl Iterator<Item = (&[u8], usize)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let c = rest.utf8_chunks().next()?.valid().chars().next();
+        let len = match (rest.strip_prefix(b"\x1b["), c) {
+            // Parameters and intermediates run up to a final byte in @..~.
+            (Some(sequence), _) => {
+                sequence.iter().position(|b| (b'@'..=b'~').contains(b)).map_or(rest.len(), |end| end + 3)
+            }
+            (None, Some(c)) => c.len_utf8(),
+            (None, None) => 1,
+        };
+        let (unit, tail) = rest.split_at(len);
+        rest = tail;
+        let width = match c {
+            _ if unit.starts_with(b"\x1b") => 0,
+            Some(c) => c.width().unwrap_or(0),
+            None => 1,
+        };
+        Some((unit, width))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{layout, width, Align, Frame, Layout};
+    use crate::style::{Color, Style};
+
+    fn lay(text: &str, options: Layout) -> String {
+        String::from_utf8(layout(text.as_bytes(), &options, <[u8]>::to_vec)).unwrap()
+    }
+
+    #[test]
+    fn test_width() {
+        assert_eq!(width(b"abc"), 3);
+        assert_eq!(width("日本語".as_bytes()), 6);
+        assert_eq!(width("ok 😀".as_bytes()), 5);
+        assert_eq!(width(b"\x1b[1;31mred\x1b[0m"), 3);
+        assert_eq!(width(b"a\xff\xe6b"), 4);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let options = Layout { width: Some(10), wrap: true, ..Layout::default() };
+        assert_eq!(lay("the quick brown fox jumps", options), "the quick\nbrown fox\njumps");
+        assert_eq!(lay("abcdefghijklmnop xy", options), "abcdefghij\nklmnop xy");
+        assert_eq!(lay("日本語のテキスト", options), "日本語のテ\nキスト");
+    }
+
+    #[test]
+    fn test_align() {
+        let options = Layout { width: Some(9), align: Align::Center, ..Layout::default() };
+        assert_eq!(lay("abc", options), "   abc");
+        let options = Layout { width: Some(9), align: Align::Right, ..Layout::default() };
+        assert_eq!(lay("abc\n日本", options), "      abc\n     日本");
+    }
+
+    #[test]
+    fn test_justify() {
+        let options = Layout { width: Some(12), wrap: true, align: Align::Justify, ..Layout::default() };
+        assert_eq!(lay("a bb ccc dddd e ff", options), "a   bb   ccc\ndddd e ff");
+    }
+
+    #[test]
+    fn test_box() {
+        let options = Layout { frame: Some(Frame::Unicode), align: Align::Center, ..Layout::default() };
+        assert_eq!(lay("hello\n日本", options), "┌───────┐\n│ hello │\n│ 日本  │\n└───────┘");
+        let options = Layout { width: Some(10), wrap: true, frame: Some(Frame::Ascii), ..Layout::default() };
+        assert_eq!(lay("one two three", options), "+--------+\n| one    |\n| two    |\n| three  |\n+--------+");
+    }
+
+    #[test]
+    fn test_raw_bytes() {
+        let options = Layout { frame: Some(Frame::Ascii), ..Layout::default() };
+        assert_eq!(layout(b"\xff", &options, <[u8]>::to_vec), b"+---+\n| \xff |\n+---+");
+    }
+
+    #[test]
+    fn test_styled_lines() {
+        let red = Style { fg: Some(Color::Red), ..Style::default() };
+        let options = Layout { width: Some(10), wrap: true, ..Layout::default() };
+        let out = layout(b"one two three", &options, |line| red.paint(line));
+        assert_eq!(out, b"\x1b[31mone two\x1b[0m\n\x1b[31mthree\x1b[0m");
+        let options = Layout { frame: Some(Frame::Ascii), align: Align::Center, ..options };
+        let out = String::from_utf8(layout(b"ab\ncdef", &options, |line| red.paint(line))).unwrap();
+        assert_eq!(out, "+--------+\n|   \x1b[31mab\x1b[0m   |\n|  \x1b[31mcdef\x1b[0m  |\n+--------+");
+        let out = lay("\x1b[32mone two\x1b[0m three", options);
+        assert_eq!(out, "+--------+\n|  \x1b[32mone\x1b[0m   |\n|  \x1b[32mtwo\x1b[0m   |\n| three  |\n+--------+");
+    }
+}
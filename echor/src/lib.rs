@@ -0,0 +1,775 @@
+use std::{
+    ffi::OsString,
+    io::{self, BufRead, BufWriter, IsTerminal, Read, Write},
+    time::Duration,
+};
+use chrono::NaiveTime;
+use clap::{CommandFactory, Parser};
+use cmdutil::{append, create, open, AtomicFile, Defaults, DefaultsArgs, Error, GenerateArgs, InputArgs, Result};
+
+mod brace;
+mod case;
+mod encode;
+mod escape;
+mod expand;
+mod format;
+mod layout;
+mod pace;
+mod quote;
+mod style;
+
+pub use brace::brace_expand;
+pub use case::{convert_case, normalize, Case, Locale, Normalization};
+pub use encode::{decode, encode, Encoding};
+pub use escape::{unescape, unescape_format, Unescaped};
+pub use expand::expand;
+pub use format::{printf, Printed};
+pub use layout::{layout, Align, Frame, Layout};
+pub use pace::{wait_until, Pace, Paced};
+pub use quote::{quote, Quoting};
+pub use style::{markup, Color, ColorChoice, Style};
+
+#[derive(Parser)]
+#[derive(Debug)]
+#[command(name = "echor")]
+#[command(author = "Ben")]
+#[command(version = "1.0.0")]
+#[command(about = "Rust echo", long_about = None)]
+#[command(args_override_self = true)]
+pub struct Cli {
+    #[arg(
+        required_unless_present_any = ["format", "template", "stdin", "from_file"],
+        value_name = "TEXT SEQUENCE",
+    )]
+    text: Vec<String>,
+    
+    #[arg(short = 'n', long, default_value_t = false, overrides_with_all = ["terminator", "null"])]
+    omit_newline: bool,
+
+    /// Put STR between arguments instead of a space (a comma with
+    /// --quote=csv); escapes are interpreted
+    #[arg(long, value_name = "STR")]
+    separator: Option<String>,
+
+    /// End the output with STR instead of a newline; escapes are interpreted
+    #[arg(long, value_name = "STR", overrides_with_all = ["omit_newline", "null"])]
+    terminator: Option<String>,
+
+    /// End the output with a NUL byte instead of a newline
+    #[arg(short = 'z', long, overrides_with_all = ["omit_newline", "terminator"])]
+    null: bool,
+
+    /// Interpret backslash escapes such as \n, \t, \xHH and \c
+    #[arg(short = 'e', overrides_with = "no_escapes")]
+    escapes: bool,
+
+    /// Do not interpret backslash escapes (the default)
+    #[arg(short = 'E', overrides_with = "escapes")]
+    no_escapes: bool,
+
+    /// Format the arguments like printf(1), reusing FORMAT while arguments remain
+    #[arg(long, value_name = "FORMAT")]
+    format: Option<String>,
+
+    /// Also read words from stdin, one per line
+    #[arg(long, conflicts_with = "from_file")]
+    stdin: bool,
+
+    /// Also read words from FILE, one per line
+    #[arg(long, value_name = "FILE")]
+    from_file: Option<String>,
+
+    /// Words read with --stdin or --from-file end with NUL, not newline
+    #[arg(short = '0', long)]
+    null_input: bool,
+
+    /// Print N words per line instead of all on one
+    #[arg(long, value_name = "N")]
+    per_line: Option<usize>,
+
+    /// Print the output N times
+    #[arg(long, value_name = "N", default_value_t = 1, conflicts_with = "repeat_forever")]
+    repeat: u64,
+
+    /// Print the output over and over until the reader goes away, like yes(1)
+    #[arg(long)]
+    repeat_forever: bool,
+
+    /// Print the output once for each number from START to END, with {} in
+    /// the arguments replaced by the number
+    #[arg(long, value_name = "START..END[..STEP]")]
+    seq: Option<String>,
+
+    /// Expand {a,b}, {1..10} and {a..z} in arguments as bash does
+    #[arg(long)]
+    brace_expand: bool,
+
+    /// Substitute environment variables: $VAR, ${VAR:-default}, ${VAR:?message}
+    #[arg(long)]
+    expand: bool,
+
+    /// Print FILE with environment variables substituted as for --expand,
+    /// and otherwise as it is
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "text", "format", "omit_newline", "separator", "terminator", "null", "escapes", "stdin",
+            "from_file", "null_input", "per_line", "repeat", "repeat_forever", "seq", "brace_expand",
+            "quote", "encode", "decode", "case", "normalize", "fg", "bg", "bold", "underline", "markup",
+            "width", "wrap", "align", "frame",
+        ],
+    )]
+    template: Option<String>,
+
+    /// Fail on undefined variables instead of expanding them to nothing
+    #[arg(long)]
+    strict: bool,
+
+    /// Print each argument escaped for the given syntax
+    #[arg(long, value_name = "SYNTAX", conflicts_with = "format")]
+    quote: Option<Quoting>,
+
+    /// Print the text encoded
+    #[arg(long, value_name = "ENCODING", conflicts_with = "decode")]
+    encode: Option<Encoding>,
+
+    /// Print the text decoded
+    #[arg(long, value_name = "ENCODING")]
+    decode: Option<Encoding>,
+
+    /// Change the case of the text
+    #[arg(long, value_name = "CASE")]
+    case: Option<Case>,
+
+    /// Use the case rules of LOCALE, such as tr for the dotted and dotless i
+    #[arg(long, value_name = "LOCALE", requires = "case")]
+    locale: Option<String>,
+
+    /// Put the text into a Unicode normalization form
+    #[arg(long, value_name = "FORM")]
+    normalize: Option<Normalization>,
+
+    /// Color the text
+    #[arg(long, value_name = "COLOR")]
+    fg: Option<Color>,
+
+    /// Color the background
+    #[arg(long, value_name = "COLOR")]
+    bg: Option<Color>,
+
+    /// Print the text in bold
+    #[arg(long)]
+    bold: bool,
+
+    /// Underline the text
+    #[arg(long)]
+    underline: bool,
+
+    /// Style parts of the text with tags such as '[bold green]ok[/] done'
+    #[arg(long)]
+    markup: bool,
+
+    /// When to use color and style codes
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: ColorChoice,
+
+    /// Lay the text out in N terminal columns, box included
+    #[arg(long, value_name = "N")]
+    width: Option<usize>,
+
+    /// Wrap words to fit the width (80 columns without --width)
+    #[arg(long)]
+    wrap: bool,
+
+    /// Place each line within the width
+    #[arg(long, value_name = "HOW", default_value = "left")]
+    align: Align,
+
+    /// Draw a box around the text
+    #[arg(
+        long = "box",
+        value_name = "STYLE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "unicode",
+    )]
+    frame: Option<Frame>,
+
+    /// Write a character at a time, pausing for DURATION (e.g. 20ms) between
+    #[arg(long, value_name = "DURATION", value_parser = pace::parse_duration)]
+    delay_char: Option<Duration>,
+
+    /// Write a line at a time, pausing for DURATION (e.g. 500ms) between
+    #[arg(long, value_name = "DURATION", value_parser = pace::parse_duration)]
+    delay_line: Option<Duration>,
+
+    /// Wait until the clock reads TIME before writing anything
+    #[arg(long, value_name = "HH:MM:SS", value_parser = pace::parse_time)]
+    at: Option<NaiveTime>,
+
+    /// Write to standard error instead of standard output
+    #[arg(long, conflicts_with_all = ["to", "append", "atomic"])]
+    stderr: bool,
+
+    /// Write to FILE, replacing its contents
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["append", "atomic"])]
+    to: Option<String>,
+
+    /// Add to the end of FILE, creating it if needed
+    #[arg(long, value_name = "FILE", conflicts_with = "atomic")]
+    append: Option<String>,
+
+    /// Replace FILE in one step by writing a temporary file beside it and
+    /// renaming it over FILE
+    #[arg(long, value_name = "FILE")]
+    atomic: Option<String>,
+
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    defaults: DefaultsArgs,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+pub fn get_args() -> Result<Cli> {
+    get_args_from(std::env::args_os())
+}
+
+pub fn get_args_from<I, T>(args: I) -> Result<Cli>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let defaults = Defaults::load::<Cli>("echor", args)?;
+    let cli = Cli::parse_from(defaults.args());
+    cli.generate.exit_if_requested(Cli::command())?;
+    cli.defaults.exit_if_print_requested(&defaults, &cli)?;
+
+    if cli.per_line == Some(0) {
+        return Err(Error::Usage("per-line must be positive".to_string()));
+    }
+
+    if let Some(seq) = &cli.seq {
+        let _ = seq_numbers(seq)?;
+    }
+
+    if cli.width == Some(0) {
+        return Err(Error::Usage("width must be positive".to_string()));
+    }
+
+    Ok(cli)
+}
+
+/// Options for [`echo`], independent of the command-line parser.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub separator: Vec<u8>,
+    pub terminator: Vec<u8>,
+    pub escapes: bool,
+    pub format: Option<String>,
+    /// Write a separate line, with its own terminator, for every this many
+    /// words.
+    pub per_line: Option<usize>,
+    pub repeat: u64,
+    pub repeat_forever: bool,
+    pub seq: Option<String>,
+    pub brace_expand: bool,
+    pub expand: bool,
+    pub strict: bool,
+    pub quote: Option<Quoting>,
+    pub encode: Option<Encoding>,
+    pub decode: Option<Encoding>,
+    pub case: Option<Case>,
+    pub locale: Locale,
+    pub normalize: Option<Normalization>,
+    pub style: Style,
+    pub markup: bool,
+    /// Whether to write color codes at all; `style` and markup tags are
+    /// ignored, and tags removed, when this is off.
+    pub color: bool,
+    pub layout: Layout,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            separator: b" ".to_vec(),
+            terminator: b"\n".to_vec(),
+            escapes: false,
+            format: None,
+            per_line: None,
+            repeat: 1,
+            repeat_forever: false,
+            seq: None,
+            brace_expand: false,
+            expand: false,
+            strict: false,
+            quote: None,
+            encode: None,
+            decode: None,
+            case: None,
+            locale: Locale::Root,
+            normalize: None,
+            style: Style::default(),
+            markup: false,
+            color: false,
+            layout: Layout::default(),
+        }
+    }
+}
+
+impl From<&Cli> for Options {
+    fn from(cli: &Cli) -> Self {
+        let terminator = match &cli.terminator {
+            _ if cli.omit_newline => Vec::new(),
+            _ if cli.null => vec![0],
+            Some(terminator) => unescape(terminator).bytes,
+            None => b"\n".to_vec(),
+        };
+        let separator = match (&cli.separator, cli.quote) {
+            (Some(separator), _) => unescape(separator).bytes,
+            (None, Some(Quoting::Csv)) => b",".to_vec(),
+            (None, _) => b" ".to_vec(),
+        };
+        Options {
+            separator,
+            terminator,
+            escapes: cli.escapes,
+            format: cli.format.clone(),
+            per_line: cli.per_line,
+            repeat: cli.repeat,
+            repeat_forever: cli.repeat_forever,
+            seq: cli.seq.clone(),
+            brace_expand: cli.brace_expand,
+            expand: cli.expand,
+            strict: cli.strict,
+            quote: cli.quote,
+            encode: cli.encode,
+            decode: cli.decode,
+            case: cli.case,
+            locale: cli.locale.as_deref().map_or(Locale::Root, Locale::from_name),
+            normalize: cli.normalize,
+            style: Style {
+                fg: cli.fg,
+                bg: cli.bg,
+                bold: cli.bold,
+                underline: cli.underline,
+            },
+            markup: cli.markup,
+            color: cli.color.enabled(cli.writes_to_terminal()),
+            layout: Layout {
+                width: cli.width,
+                wrap: cli.wrap,
+                align: cli.align,
+                frame: cli.frame,
+            },
+        }
+    }
+}
+
+impl Cli {
+    fn writes_to_terminal(&self) -> bool {
+        if self.to.is_some() || self.append.is_some() || self.atomic.is_some() {
+            false
+        } else if self.stderr {
+            io::stderr().is_terminal()
+        } else {
+            io::stdout().is_terminal()
+        }
+    }
+}
+
+pub fn run(cli: Cli) -> Result<()> {
+    let options = Options::from(&cli);
+    let pace = Pace { char: cli.delay_char, line: cli.delay_line };
+    if let Some(time) = cli.at {
+        wait_until(time);
+    }
+
+    if let Some(path) = &cli.atomic {
+        let mut file = AtomicFile::create(path)?;
+        let mut writer = Paced::new(&mut file, pace);
+        let errors = write_output(&cli, &options, &mut writer)?;
+        writer.flush()?;
+        file.commit()?;
+        return report(&errors);
+    }
+
+    let writer: Box<dyn Write> = match (&cli.to, &cli.append) {
+        (Some(path), _) => create(Some(path))?,
+        (None, Some(path)) => append(path)?,
+        (None, None) if cli.stderr => Box::new(BufWriter::new(io::stderr().lock())),
+        (None, None) => Box::new(cmdutil::stdout()),
+    };
+    let mut writer = Paced::new(writer, pace);
+    let errors = write_output(&cli, &options, &mut writer)?;
+    writer.flush()?;
+    report(&errors)
+}
+
+/// Reports the arguments `--format` could not use, which fails the run
+/// though the output was written in full.
+fn report(errors: &[String]) -> Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    for error in errors {
+        eprintln!("{error}");
+    }
+    Err(Error::InputsFailed)
+}
+
+/// Writes what `cli` asks for, returning the errors [`echo`] returns.
+fn write_output<W: Write>(cli: &Cli, options: &Options, writer: &mut W) -> Result<Vec<String>> {
+    match &cli.template {
+        Some(path) => {
+            let mut template = String::new();
+            open(path, false)?
+                .read_to_string(&mut template)
+                .map_err(|source| Error::Open { path: path.clone(), source })?;
+            // A malformed `${` is in the file, not on the command line.
+            let expanded = expand(&template, &env_var, options.strict).map_err(|err| match err {
+                Error::Usage(message) => Error::Invalid(format!("{path}: {message}")),
+                err => err,
+            })?;
+            writer.write_all(expanded.as_bytes())?;
+            Ok(Vec::new())
+        }
+        None => {
+            let input = match (&cli.from_file, cli.stdin) {
+                (Some(path), _) => Some(path.as_str()),
+                (None, true) => Some("-"),
+                (None, false) => None,
+            };
+            let mut text = cli.text.clone();
+            if let Some(path) = input {
+                let delimiter = if cli.null_input { 0 } else { b'\n' };
+                let reader = open(path, cli.input.decompress())?;
+                let words = read_words(reader, delimiter).map_err(|source| Error::Open {
+                    path: path.to_string(),
+                    source,
+                })?;
+                text.extend(words);
+            }
+            echo(&text, writer, options)
+        }
+    }
+}
+
+/// Reads the words in `reader`, each ended by `delimiter` except perhaps the
+/// last.
+pub fn read_words<R: BufRead>(reader: R, delimiter: u8) -> io::Result<Vec<String>> {
+    reader
+        .split(delimiter)
+        .map(|word| String::from_utf8(word?).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)))
+        .collect()
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var_os(name).map(|value| value.to_string_lossy().into_owned())
+}
+
+/// Writes `text` to `writer` joined by `options.separator` and followed by
+/// `options.terminator`, or as one such line per `options.per_line` words.
+///
+/// With `options.brace_expand`, each word is first split by [`brace_expand`].
+/// With `options.expand`, environment variables in each word are then
+/// substituted by [`expand`]. With `options.escapes`, each word then goes
+/// through [`unescape`] and a `\c` ends the output on the spot, newline
+/// included. With `options.quote`, each word is finally escaped by
+/// [`quote`]. With `options.format`, `text` is handed to [`printf`] instead
+/// and no terminator is added. The arguments it needed as numbers but could
+/// not read as ones are written as zero, and the messages about them are
+/// returned, once however often the output is repeated.
+///
+/// Each line is then decoded by [`decode`] if `options.decode` is set,
+/// converted to `options.case` by [`convert_case`] and to
+/// `options.normalize` by [`normalize`], encoded by [`encode`] if
+/// `options.encode` is set, and with `options.markup` its tags rendered by
+/// [`markup`]. Last, it is wrapped, aligned and boxed by [`layout`] as
+/// `options.layout` asks, each resulting line drawn in `options.style`.
+///
+/// All of that is written once for each number in `options.seq`, with `{}`
+/// in `text` replaced by the number, and the whole lot `options.repeat`
+/// times, or until writing fails with `options.repeat_forever`.
+pub fn echo<S: AsRef<str>, W: Write>(text: &[S], writer: &mut W, options: &Options) -> Result<Vec<String>> {
+    let Some(seq) = &options.seq else {
+        let mut once = Vec::new();
+        let errors = echo_once(text, &mut once, options)?;
+        if options.repeat_forever {
+            repeat_forever(&once, writer)?;
+            return Ok(errors);
+        }
+        for _ in 0..options.repeat {
+            writer.write_all(&once)?;
+        }
+        return Ok(errors);
+    };
+
+    let mut errors = Vec::new();
+    let mut pass = 0;
+    while options.repeat_forever || pass < options.repeat {
+        for number in seq_numbers(seq)? {
+            let text: Vec<String> = text.iter().map(|word| word.as_ref().replace("{}", &number)).collect();
+            let once = echo_once(&text, writer, options)?;
+            // Later passes only repeat the same errors.
+            if pass == 0 {
+                errors.extend(once);
+            }
+        }
+        pass += 1;
+    }
+    Ok(errors)
+}
+
+/// Parses a `--seq` range into its numbers.
+fn seq_numbers(seq: &str) -> Result<Box<dyn Iterator<Item = String>>> {
+    brace::sequence(seq).ok_or_else(|| Error::Usage(format!("invalid sequence '{seq}', expected START..END[..STEP]")))
+}
+
+/// Writes `once` until the writer fails, which is how this ends when the
+/// reader closes the pipe.
+fn repeat_forever<W: Write>(once: &[u8], writer: &mut W) -> Result<()> {
+    if once.is_empty() {
+        return Ok(());
+    }
+    // Like yes(1), batch many copies per write for throughput.
+    let buffer = once.repeat((64 * 1024 / once.len()).max(1));
+    loop {
+        writer.write_all(&buffer)?;
+    }
+}
+
+/// Writes `text` once, returning the errors in the `--format` arguments.
+fn echo_once<S: AsRef<str>, W: Write>(text: &[S], writer: &mut W, options: &Options) -> Result<Vec<String>> {
+    let text = words(text, options)?;
+    if let Some(format) = &options.format {
+        let printed = printf(format, &text)?;
+        write_styled(writer, &convert(printed.bytes, options), options)?;
+        return Ok(printed.errors);
+    }
+
+    let per_line = options.per_line.unwrap_or(text.len()).max(1);
+    let mut lines = text.chunks(per_line);
+    // No words still make one, empty, line.
+    let first = lines.next().unwrap_or_default();
+    for line in [first].into_iter().chain(lines) {
+        let (mut body, terminate) = join(line, options);
+        if let Some(encoding) = options.decode {
+            body = decode(&body, encoding)?;
+        }
+        body = convert(body, options);
+        if let Some(encoding) = options.encode {
+            body = encode(&body, encoding);
+        }
+        write_styled(writer, &body, options)?;
+        if !terminate {
+            break;
+        }
+        writer.write_all(&options.terminator)?;
+    }
+    Ok(Vec::new())
+}
+
+/// Applies `--case` and then `--normalize`.
+fn convert(body: Vec<u8>, options: &Options) -> Vec<u8> {
+    if options.case.is_none() && options.normalize.is_none() {
+        return body;
+    }
+    let mut text = String::from_utf8_lossy(&body).into_owned();
+    if let Some(case) = options.case {
+        text = convert_case(&text, case, options.locale);
+    }
+    if let Some(form) = options.normalize {
+        text = normalize(&text, form);
+    }
+    text.into_bytes()
+}
+
+fn write_styled<W: Write>(writer: &mut W, body: &[u8], options: &Options) -> Result<()> {
+    // Markup tags must be rendered before layout can measure the text;
+    // `layout` keeps the colors they leave open to each line.
+    let body = if options.markup { markup(body, options.style, options.color) } else { body.to_vec() };
+    let paint = |text: &[u8]| {
+        if options.color && !options.markup {
+            options.style.paint(text)
+        } else {
+            text.to_vec()
+        }
+    };
+    if options.layout.is_active() {
+        writer.write_all(&layout(&body, &options.layout, paint))?;
+    } else {
+        writer.write_all(&paint(&body))?;
+    }
+    Ok(())
+}
+
+/// Joins `text` with the separator, returning the bytes and whether the
+/// terminator should follow them, which it shouldn't after a `\c`.
+fn join(text: &[String], options: &Options) -> (Vec<u8>, bool) {
+    let mut body = Vec::new();
+    for (idx, word) in text.iter().enumerate() {
+        if idx > 0 {
+            body.extend_from_slice(&options.separator);
+        }
+        let (bytes, stop) = if options.escapes {
+            let word = unescape(word);
+            (word.bytes, word.stop)
+        } else {
+            (word.as_bytes().to_vec(), false)
+        };
+        match options.quote {
+            Some(quoting) => body.extend_from_slice(&quote(&bytes, quoting)),
+            None => body.extend_from_slice(&bytes),
+        }
+        if stop {
+            return (body, false);
+        }
+    }
+    (body, true)
+}
+
+/// Applies the word-level transformations in `options` to `text`.
+fn words<S: AsRef<str>>(text: &[S], options: &Options) -> Result<Vec<String>> {
+    text.iter()
+        .flat_map(|word| {
+            if options.brace_expand {
+                brace_expand(word.as_ref())
+            } else {
+                vec![word.as_ref().to_string()]
+            }
+        })
+        .map(|word| {
+            if options.expand {
+                expand(&word, &env_var, options.strict)
+            } else {
+                Ok(word)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{echo, read_words, Case, Locale, Normalization, Options};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_echo() {
+        let mut out = Vec::new();
+        echo(&["Hello", " there"], &mut out, &Options::default()).unwrap();
+        assert_eq!(out, b"Hello  there\n");
+    }
+
+    #[test]
+    fn test_echo_omit_newline() {
+        let mut out = Vec::new();
+        let options = Options { terminator: Vec::new(), ..Options::default() };
+        echo(&["Hello"], &mut out, &options).unwrap();
+        assert_eq!(out, b"Hello");
+    }
+
+    #[test]
+    fn test_echo_separator_terminator() {
+        let mut out = Vec::new();
+        let options = Options { separator: b", ".to_vec(), terminator: vec![0], ..Options::default() };
+        echo(&["a", "b"], &mut out, &options).unwrap();
+        assert_eq!(out, b"a, b\0");
+    }
+
+    #[test]
+    fn test_echo_per_line() {
+        let mut out = Vec::new();
+        let options = Options { per_line: Some(2), ..Options::default() };
+        echo(&["a", "b", "c", "d", "e"], &mut out, &options).unwrap();
+        assert_eq!(out, b"a b\nc d\ne\n");
+
+        let mut out = Vec::new();
+        echo(&[] as &[&str], &mut out, &options).unwrap();
+        assert_eq!(out, b"\n");
+    }
+
+    #[test]
+    fn test_echo_repeat_and_seq() {
+        let mut out = Vec::new();
+        let options = Options { repeat: 3, ..Options::default() };
+        echo(&["y"], &mut out, &options).unwrap();
+        assert_eq!(out, b"y\ny\ny\n");
+
+        let mut out = Vec::new();
+        let options = Options { repeat: 2, seq: Some("3..1".to_string()), ..Options::default() };
+        echo(&["n{}", "x"], &mut out, &options).unwrap();
+        assert_eq!(out, b"n3 x\nn2 x\nn1 x\nn3 x\nn2 x\nn1 x\n");
+
+        let options = Options { seq: Some("1..".to_string()), ..Options::default() };
+        assert!(echo(&["x"], &mut Vec::new(), &options).is_err());
+    }
+
+    #[test]
+    fn test_echo_format_errors() {
+        let mut out = Vec::new();
+        let options = Options { format: Some("%d|".to_string()), repeat: 2, ..Options::default() };
+        let errors = echo(&["1", "x"], &mut out, &options).unwrap();
+        assert_eq!(out, b"1|0|1|0|");
+        assert_eq!(errors, ["'x': expected a numeric value"]);
+
+        let mut out = Vec::new();
+        let options = Options { seq: Some("1..2".to_string()), ..options };
+        let errors = echo(&["{}", "x"], &mut out, &options).unwrap();
+        assert_eq!(out, b"1|0|2|0|1|0|2|0|");
+        assert_eq!(errors, ["'x': expected a numeric value", "'x': expected a numeric value"]);
+    }
+
+    #[test]
+    fn test_echo_repeat_forever() {
+        let mut out = [0u8; 100_000];
+        let options = Options { repeat_forever: true, ..Options::default() };
+        let err = echo(&["ab"], &mut &mut out[..], &options).unwrap_err();
+        assert!(err.to_string().contains("failed to write whole buffer"));
+        assert!(out.chunks(3).all(|chunk| b"ab\n".starts_with(chunk)));
+    }
+
+    #[test]
+    fn test_echo_case() {
+        let mut out = Vec::new();
+        let options = Options { case: Some(Case::Snake), ..Options::default() };
+        echo(&["Hello", "World"], &mut out, &options).unwrap();
+        assert_eq!(out, b"hello_world\n");
+
+        let mut out = Vec::new();
+        let options = Options {
+            case: Some(Case::Upper),
+            locale: Locale::Turkic,
+            normalize: Some(Normalization::Nfd),
+            ..Options::default()
+        };
+        echo(&["izmir"], &mut out, &options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "I\u{307}ZMI\u{307}R\n");
+    }
+
+    #[test]
+    fn test_read_words() {
+        let words = read_words(Cursor::new("a b\n\nc\n"), b'\n').unwrap();
+        assert_eq!(words, ["a b", "", "c"]);
+        let words = read_words(Cursor::new("a\0b"), 0).unwrap();
+        assert_eq!(words, ["a", "b"]);
+        assert!(read_words(Cursor::new(b"\xff\n"), b'\n').is_err());
+    }
+
+    #[test]
+    fn test_echo_escapes() {
+        let mut out = Vec::new();
+        let options = Options { escapes: true, ..Options::default() };
+        echo(&["a\\tb", "c\\cd", "e"], &mut out, &options).unwrap();
+        assert_eq!(out, b"a\tb c");
+
+        let mut out = Vec::new();
+        echo(&["a\\tb"], &mut out, &Options::default()).unwrap();
+        assert_eq!(out, b"a\\tb\n");
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    io::{self, Write},
+    mem, thread,
+    time::Duration,
+};
+
+use chrono::{Local, NaiveTime};
+
+/// How long [`Paced`] pauses between what it writes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Pace {
+    /// Between characters.
+    pub char: Option<Duration>,
+    /// Between lines, in place of the pause between characters.
+    pub line: Option<Duration>,
+}
+
+impl Pace {
+    /// Whether [`Paced`] would pause at all.
+    pub fn is_active(&self) -> bool {
+        self.char.is_some() || self.line.is_some()
+    }
+}
+
+/// A writer that passes text on a character at a time with `pace.char`, or
+/// a line at a time with only `pace.line`, flushing after each and pausing
+/// before the next. ANSI escape sequences go out whole and without a pause.
+/// Text held back for an incomplete character or line is written by
+/// `flush`.
+pub struct Paced<W: Write> {
+    inner: W,
+    pace: Pace,
+    pending: Vec<u8>,
+    started: bool,
+    line_start: bool,
+}
+
+impl<W: Write> Paced<W> {
+    pub fn new(inner: W, pace: Pace) -> Paced<W> {
+        Paced { inner, pace, pending: Vec::new(), started: false, line_start: false }
+    }
+
+    fn emit(&mut self, unit: &[u8]) -> io::Result<()> {
+        if !is_escape(unit) {
+            let pause = if self.line_start { self.pace.line.or(self.pace.char) } else { self.pace.char };
+            if let (true, Some(pause)) = (self.started, pause) {
+                thread::sleep(pause);
+            }
+            self.started = true;
+            self.line_start = unit.ends_with(b"\n");
+        }
+        self.inner.write_all(unit)?;
+        self.inner.flush()
+    }
+
+    /// The length of the unit `bytes` starts with, or `None` if it may not
+    /// be complete yet.
+    fn unit_len(&self, bytes: &[u8]) -> Option<usize> {
+        let &first = bytes.first()?;
+        if self.pace.char.is_none() {
+            return bytes.iter().position(|&b| b == b'\n').map(|pos| pos + 1);
+        }
+        if first == b'\x1b' && bytes.len() == 1 {
+            return None;
+        }
+        if let Some(sequence) = bytes.strip_prefix(b"\x1b[") {
+            return sequence.iter().position(|b| (0x40..=0x7e).contains(b)).map(|end| end + 3);
+        }
+        let len = match first {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        let continuation = bytes[1..].iter().take(len - 1).take_while(|b| (0x80..=0xbf).contains(*b)).count();
+        if continuation == len - 1 {
+            Some(len)
+        } else if continuation == bytes.len() - 1 {
+            None
+        } else {
+            // Invalid UTF-8 goes out a byte at a time.
+            Some(1)
+        }
+    }
+}
+
+impl<W: Write> Write for Paced<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.pace.is_active() {
+            return self.inner.write(buf);
+        }
+        self.pending.extend_from_slice(buf);
+        let pending = mem::take(&mut self.pending);
+        let mut rest = &pending[..];
+        while let Some(len) = self.unit_len(rest) {
+            let (unit, tail) = rest.split_at(len);
+            self.emit(unit)?;
+            rest = tail;
+        }
+        self.pending = rest.to_vec();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let pending = mem::take(&mut self.pending);
+            self.emit(&pending)?;
+        }
+        self.inner.flush()
+    }
+}
+
+/// Whether `unit` is a single ANSI escape sequence and nothing more, as
+/// opposed to text that merely starts with one, such as a coloured line.
+fn is_escape(unit: &[u8]) -> bool {
+    let Some(sequence) = unit.strip_prefix(b"\x1b[") else {
+        return false;
+    };
+    sequence.iter().position(|b| (0x40..=0x7e).contains(b)).map(|end| end + 1) == Some(sequence.len())
+}
+
+/// Parses a duration such as `20ms`, `1.5s`, `2m` or `1h`. A bare number is
+/// in seconds.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let scale = match unit {
+        "us" | "µs" => 1e-6,
+        "ms" => 1e-3,
+        "" | "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => f64::NAN,
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .and_then(|number| Duration::try_from_secs_f64(number * scale).ok())
+        .ok_or_else(|| "expected a duration such as 20ms, 1.5s or 2m".to_string())
+}
+
+/// Parses a wall-clock time given as `HH:MM:SS` or `HH:MM`.
+pub fn parse_time(text: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(text, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(text, "%H:%M"))
+        .map_err(|_| "expected a time as HH:MM:SS or HH:MM".to_string())
+}
+
+/// Sleeps until the local clock next reads `time`, which is tomorrow if it
+/// has already passed today.
+pub fn wait_until(time: NaiveTime) {
+    let now = Local::now();
+    let mut day = now.date_naive();
+    for _ in 0..3 {
+        // A time skipped by a daylight saving change doesn't happen that day.
+        if let Some(target) = day.and_time(time).and_local_timezone(Local).earliest() {
+            if target >= now {
+                thread::sleep((target - now).to_std().unwrap_or_default());
+                return;
+            }
+        }
+        let Some(next) = day.succ_opt() else {
+            return;
+        };
+        day = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_duration, parse_time, Pace, Paced};
+    use std::{
+        io::{self, Write},
+        time::{Duration, Instant},
+    };
+
+    /// Records each write separately.
+    #[derive(Default)]
+    struct Writes(Vec<Vec<u8>>);
+
+    impl Write for &mut Writes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn pace(chunks: &[&[u8]], pace: Pace) -> Vec<String> {
+        let mut writes = Writes::default();
+        let mut paced = Paced::new(&mut writes, pace);
+        for chunk in chunks {
+            paced.write_all(chunk).unwrap();
+        }
+        paced.flush().unwrap();
+        writes.0.iter().map(|write| String::from_utf8_lossy(write).into_owned()).collect()
+    }
+
+    #[test]
+    fn test_paced_chars() {
+        let options = Pace { char: Some(Duration::ZERO), line: None };
+        let units = pace(&[b"a\xe6\x97", b"\xa5\x1b[3", b"1mb\n"], options);
+        assert_eq!(units, ["a", "日", "\x1b[31m", "b", "\n"]);
+        assert_eq!(pace(&[b"\xff\xe6x"], options), ["\u{fffd}", "\u{fffd}", "x"]);
+    }
+
+    #[test]
+    fn test_paced_lines() {
+        let options = Pace { char: None, line: Some(Duration::from_millis(20)) };
+        let start = Instant::now();
+        assert_eq!(pace(&[b"one\ntw", b"o\nthree"], options), ["one\n", "two\n", "three"]);
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(pace(&[b"as is"], Pace::default()), ["as is"]);
+    }
+
+    #[test]
+    fn test_paced_colored_lines() {
+        let options = Pace { char: None, line: Some(Duration::from_millis(20)) };
+        let start = Instant::now();
+        let units = pace(&[b"\x1b[31mone\x1b[0m\n\x1b[31mtwo\x1b[0m\n\x1b[31mthree\x1b[0m\n"], options);
+        assert_eq!(units, ["\x1b[31mone\x1b[0m\n", "\x1b[31mtwo\x1b[0m\n", "\x1b[31mthree\x1b[0m\n"]);
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("20ms"), Ok(Duration::from_millis(20)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert!(parse_duration("ms").is_err());
+        assert!(parse_duration("5 s").is_err());
+        assert!(parse_duration("1d").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("09:30:15").unwrap().to_string(), "09:30:15");
+        assert_eq!(parse_time("23:05").unwrap().to_string(), "23:05:00");
+        assert!(parse_time("24:00").is_err());
+        assert!(parse_time("noon").is_err());
+    }
+}
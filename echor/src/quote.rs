@@ -0,0 +1,141 @@
+use clap::ValueEnum;
+
+/// The syntax [`quote`] escapes a word for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Quoting {
+    /// POSIX shell word, single-quoted when needed
+    Shell,
+    /// JSON string literal
+    Json,
+    /// C string literal
+    C,
+    /// RFC 4180 CSV field, quoted when needed
+    Csv,
+}
+
+/// Escapes `word` so that it reads back unchanged in the `quoting` syntax.
+pub fn quote(word: &[u8], quoting: Quoting) -> Vec<u8> {
+    match quoting {
+        Quoting::Shell => shell(word),
+        Quoting::Json => json(word),
+        Quoting::C => c(word),
+        Quoting::Csv => csv(word),
+    }
+}
+
+fn shell(word: &[u8]) -> Vec<u8> {
+    let safe = |b: &u8| b.is_ascii_alphanumeric() || b"_@%+=:,./-".contains(b);
+    if !word.is_empty() && word.iter().all(safe) {
+        return word.to_vec();
+    }
+
+    // Nothing is special inside single quotes except the quote itself,
+    // which has to be closed, escaped and reopened.
+    let mut out = vec![b'\''];
+    for &b in word {
+        match b {
+            b'\'' => out.extend_from_slice(br"'\''"),
+            _ => out.push(b),
+        }
+    }
+    out.push(b'\'');
+    out
+}
+
+fn json(word: &[u8]) -> Vec<u8> {
+    let mut out = String::from('"');
+    for chunk in word.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                '\x08' => out.push_str("\\b"),
+                '\x0c' => out.push_str("\\f"),
+                c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+        // JSON text must be Unicode, so bytes that aren't UTF-8 can't survive.
+        if !chunk.invalid().is_empty() {
+            out.push_str("\\ufffd");
+        }
+    }
+    out.push('"');
+    out.into_bytes()
+}
+
+fn c(word: &[u8]) -> Vec<u8> {
+    let mut out = vec![b'"'];
+    for &b in word {
+        match b {
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'\x07' => out.extend_from_slice(b"\\a"),
+            b'\x08' => out.extend_from_slice(b"\\b"),
+            b'\x0c' => out.extend_from_slice(b"\\f"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            b'\x0b' => out.extend_from_slice(b"\\v"),
+            // Three octal digits, unlike \x, can't swallow a following digit.
+            b' '..=b'~' => out.push(b),
+            _ => out.extend_from_slice(format!("\\{b:03o}").as_bytes()),
+        }
+    }
+    out.push(b'"');
+    out
+}
+
+fn csv(word: &[u8]) -> Vec<u8> {
+    if !word.iter().any(|b| b",\"\r\n".contains(b)) {
+        return word.to_vec();
+    }
+
+    let mut out = vec![b'"'];
+    for &b in word {
+        if b == b'"' {
+            out.push(b'"');
+        }
+        out.push(b);
+    }
+    out.push(b'"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quote, Quoting};
+
+    fn check(quoting: Quoting, word: &[u8], expected: &str) {
+        assert_eq!(String::from_utf8_lossy(&quote(word, quoting)), expected, "quoting {word:?}");
+    }
+
+    #[test]
+    fn test_quote_shell() {
+        check(Quoting::Shell, b"plain/file-1.txt", "plain/file-1.txt");
+        check(Quoting::Shell, b"", "''");
+        check(Quoting::Shell, b"it's a\nline", "'it'\\''s a\nline'");
+        check(Quoting::Shell, "$HOME é".as_bytes(), "'$HOME é'");
+    }
+
+    #[test]
+    fn test_quote_json() {
+        check(Quoting::Json, "say \"hi\"\\\n\té\x01".as_bytes(), r#""say \"hi\"\\\n\té\u0001""#);
+        check(Quoting::Json, b"caf\xe9", r#""caf\ufffd""#);
+    }
+
+    #[test]
+    fn test_quote_c() {
+        check(Quoting::C, "a\"b\\\n\x1b1é".as_bytes(), r#""a\"b\\\n\0331\303\251""#);
+    }
+
+    #[test]
+    fn test_quote_csv() {
+        check(Quoting::Csv, b"plain field", "plain field");
+        check(Quoting::Csv, b"a,b", "\"a,b\"");
+        check(Quoting::Csv, b"say \"hi\"\nbye", "\"say \"\"hi\"\"\nbye\"");
+    }
+}
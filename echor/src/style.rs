@@ -0,0 +1,233 @@
+use clap::ValueEnum;
+
+/// A terminal color for `--fg`, `--bg` and markup tags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl Color {
+    /// The SGR parameter for this color in the foreground; the background
+    /// one is 10 more.
+    fn code(self) -> u8 {
+        match self {
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::BrightBlack => 90,
+            Color::BrightRed => 91,
+            Color::BrightGreen => 92,
+            Color::BrightYellow => 93,
+            Color::BrightBlue => 94,
+            Color::BrightMagenta => 95,
+            Color::BrightCyan => 96,
+            Color::BrightWhite => 97,
+        }
+    }
+}
+
+/// When to write color codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorChoice {
+    /// Only when writing to a terminal and NO_COLOR is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output going to a terminal or not, as `terminal` says.
+    pub fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => terminal && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+        }
+    }
+}
+
+/// Text attributes written as one ANSI SGR sequence.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+
+    /// The escape sequence switching from plain text to this style.
+    fn sgr(&self) -> Vec<u8> {
+        if self.is_plain() {
+            return Vec::new();
+        }
+        let mut params = Vec::new();
+        if self.bold {
+            params.push(1);
+        }
+        if self.underline {
+            params.push(4);
+        }
+        if let Some(fg) = self.fg {
+            params.push(fg.code());
+        }
+        if let Some(bg) = self.bg {
+            params.push(bg.code() + 10);
+        }
+        let params: Vec<String> = params.iter().map(u8::to_string).collect();
+        format!("\x1b[{}m", params.join(";")).into_bytes()
+    }
+
+    /// Wraps `text` in this style, or returns it untouched if there is no
+    /// style to apply.
+    pub fn paint(&self, text: &[u8]) -> Vec<u8> {
+        if self.is_plain() || text.is_empty() {
+            return text.to_vec();
+        }
+        let mut out = self.sgr();
+        out.extend_from_slice(text);
+        out.extend_from_slice(RESET);
+        out
+    }
+
+    /// This style with the attributes named in a markup tag such as
+    /// `bold red on black` added, or `None` if the tag is not understood.
+    fn with_tag(mut self, tag: &str) -> Option<Style> {
+        let mut words = tag.split_whitespace();
+        if tag.trim().is_empty() {
+            return None;
+        }
+        while let Some(word) = words.next() {
+            match word {
+                "bold" => self.bold = true,
+                "underline" => self.underline = true,
+                "on" => self.bg = Some(Color::from_str(words.next()?, true).ok()?),
+                color => self.fg = Some(Color::from_str(color, true).ok()?),
+            }
+        }
+        Some(self)
+    }
+}
+
+const RESET: &[u8] = b"\x1b[0m";
+
+/// Renders inline markup in `text` on top of `base`.
+///
+/// `[green]ok[/]` colors "ok"; a tag may combine `bold`, `underline`, a
+/// color and `on COLOR` for the background. `[/]` or `[/anything]` closes the
+/// innermost open tag and `[[` is a literal `[`. Brackets that don't form a
+/// known tag are left alone. With `color` off the tags are only removed.
+pub fn markup(text: &[u8], base: Style, color: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut stack = vec![base];
+    // The style last switched to in `out`. Switching waits until there is
+    // text to show in the new style, so empty spans leave no codes behind.
+    let mut shown = Style::default();
+    let mut rest = text;
+
+    let mut write = |out: &mut Vec<u8>, style: Style, bytes: &[u8]| {
+        if bytes.is_empty() {
+            return;
+        }
+        if color && shown != style {
+            if !shown.is_plain() {
+                out.extend_from_slice(RESET);
+            }
+            out.extend_from_slice(&style.sgr());
+            shown = style;
+        }
+        out.extend_from_slice(bytes);
+    };
+    while let Some(at) = rest.iter().position(|&b| b == b'[') {
+        let current = *stack.last().unwrap_or(&base);
+        write(&mut out, current, &rest[..at]);
+        rest = &rest[at..];
+
+        if rest.starts_with(b"[[") {
+            write(&mut out, current, b"[");
+            rest = &rest[2..];
+            continue;
+        }
+        let end = rest.iter().position(|&b| b == b']');
+        let tag = end.and_then(|end| std::str::from_utf8(&rest[1..end]).ok());
+        let known = match tag {
+            Some(tag) if tag.starts_with('/') && stack.len() > 1 => stack.pop().is_some(),
+            Some(tag) => current.with_tag(tag).inspect(|&style| stack.push(style)).is_some(),
+            None => false,
+        };
+        match (known, end) {
+            (true, Some(end)) => rest = &rest[end + 1..],
+            _ => {
+                write(&mut out, current, b"[");
+                rest = &rest[1..];
+            }
+        }
+    }
+    write(&mut out, *stack.last().unwrap_or(&base), rest);
+    if color && !shown.is_plain() {
+        out.extend_from_slice(RESET);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{markup, Color, Style};
+
+    fn render(text: &str, color: bool) -> String {
+        String::from_utf8(markup(text.as_bytes(), Style::default(), color)).unwrap()
+    }
+
+    #[test]
+    fn test_paint() {
+        let style = Style { fg: Some(Color::Red), bg: Some(Color::Black), bold: true, underline: true };
+        assert_eq!(style.paint(b"hi"), b"\x1b[1;4;31;40mhi\x1b[0m");
+        assert_eq!(Style::default().paint(b"hi"), b"hi");
+    }
+
+    #[test]
+    fn test_markup() {
+        assert_eq!(render("[green]ok[/] done", true), "\x1b[32mok\x1b[0m done");
+        assert_eq!(render("[bold red on bright-white]x", true), "\x1b[1;31;107mx\x1b[0m");
+        assert_eq!(render("[green]ok[/] done", false), "ok done");
+    }
+
+    #[test]
+    fn test_markup_literal_brackets() {
+        assert_eq!(render("a[1] [[green] [/] [unknown] [", false), "a[1] [green] [/] [unknown] [");
+    }
+
+    #[test]
+    fn test_markup_nested() {
+        let base = Style { bold: true, ..Style::default() };
+        let out = markup(b"[red]a[underline]b[/]c[/]d", base, true);
+        assert_eq!(out, b"\x1b[1;31ma\x1b[0m\x1b[1;4;31mb\x1b[0m\x1b[1;31mc\x1b[0m\x1b[1md\x1b[0m");
+        assert_eq!(markup(b"[red][/]x[blue]", base, true), b"\x1b[1mx\x1b[0m");
+        assert_eq!(render("[red][/]", true), "");
+    }
+}
@@ -5,9 +5,17 @@ use predicates::prelude::*;
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
+/// The tool under test, with neither the developer's own config file nor
+/// `ECHOR_OPTS` adding defaults.
+fn cmd() -> Result<Command, Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("echor")?;
+    cmd.env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR")).env_remove("ECHOR_OPTS");
+    Ok(cmd)
+}
+
 #[test]
 fn dies_no_args() {
-    match Command::cargo_bin("echor") {
+    match cmd() {
         Err(e) => {
             println!("Failed to execute process: {}", e);
             panic!("Failed to execute process: {}", e)
@@ -16,7 +24,7 @@ fn dies_no_args() {
             println!("Executing: {:?}", cmd);
             cmd.assert()
                 .failure()
-                .stderr(predicate::str::contains("The following required arguments were not provided:"))
+                .stderr(predicate::str::contains("the following required arguments were not provided:"))
                 .stderr(predicate::str::contains("TEXT"));
         }
     }
@@ -24,7 +32,7 @@ fn dies_no_args() {
 
 fn run(args: &[&str], expected_file: &str) -> TestResult {
     let expected = fs::read_to_string(expected_file)?;
-    Command::cargo_bin("echor")?
+    cmd()?
         .args(args)
         .assert()
         .success()
@@ -50,4 +58,540 @@ fn hello3() -> TestResult {
 #[test]
 fn hello4() -> TestResult {
     run(&["-n", "Hello", "there"], "tests/expected/hello2.n.txt")
-}
\ No newline at end of file
+}
+
+#[test]
+fn escapes() -> TestResult {
+    run(&["-e", "tab\\there", "oct\\0101\\x42"], "tests/expected/escapes.e.txt")
+}
+
+#[test]
+fn escapes_stop_output() -> TestResult {
+    run(&["-e", "stop\\c", "never"], "tests/expected/escapes.c.txt")
+}
+
+#[test]
+fn no_escapes_wins_when_last() -> TestResult {
+    run(&["-e", "-E", "tab\\there"], "tests/expected/escapes.E.txt")
+}
+
+#[test]
+fn format() -> TestResult {
+    cmd()?
+        .args(["--format", "%-10s|%5d|%.2f\\n", "name", "42", "3.14159", "x"])
+        .assert()
+        .success()
+        .stdout("name      |   42|3.14\nx         |    0|0.00\n");
+    Ok(())
+}
+
+#[test]
+fn format_without_arguments() -> TestResult {
+    cmd()?
+        .args(["--format", "100%%\\n"])
+        .assert()
+        .success()
+        .stdout("100%\n");
+    Ok(())
+}
+
+#[test]
+fn format_reports_bad_number() -> TestResult {
+    cmd()?
+        .args(["--format", "%d\\n", "abc", "2"])
+        .assert()
+        .code(1)
+        .stdout("0\n2\n")
+        .stderr("'abc': expected a numeric value\n");
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("out.txt");
+    cmd()?
+        .args(["--atomic", path.to_str().unwrap(), "--format", "%d|%s\\n", "abc", "x"])
+        .assert()
+        .code(1);
+    assert_eq!(fs::read_to_string(&path)?, "0|x\n");
+    Ok(())
+}
+
+#[test]
+fn format_rejects_bad_format() -> TestResult {
+    cmd()?
+        .args(["--format", "%z", "abc"])
+        .assert()
+        .code(2)
+        .stderr("Error: %z: invalid conversion specification\n");
+    Ok(())
+}
+
+#[test]
+fn format_octal_escapes() -> TestResult {
+    cmd()?
+        .args(["--format", "\\101\\n"])
+        .assert()
+        .success()
+        .stdout("A\n");
+    Ok(())
+}
+
+#[test]
+fn separator_and_terminator() -> TestResult {
+    cmd()?
+        .args(["--separator", "\\t", "--terminator", ";\\n", "a", "b"])
+        .assert()
+        .success()
+        .stdout("a\tb;\n");
+    Ok(())
+}
+
+#[test]
+fn null_terminated_list() -> TestResult {
+    cmd()?
+        .args(["-z", "--separator", "\\0", "a.txt", "b.txt"])
+        .assert()
+        .success()
+        .stdout("a.txt\0b.txt\0");
+    Ok(())
+}
+
+#[test]
+fn last_terminator_wins() -> TestResult {
+    cmd()?
+        .args(["-z", "-n", "a"])
+        .assert()
+        .success()
+        .stdout("a");
+
+    cmd()?
+        .args(["-n", "--terminator", "!", "a"])
+        .assert()
+        .success()
+        .stdout("a!");
+    Ok(())
+}
+
+#[test]
+fn expand() -> TestResult {
+    cmd()?
+        .env_remove("APP")
+        .env("ENV", "prod")
+        .args(["--expand", "Deploying ${APP:-web} to $ENV"])
+        .assert()
+        .success()
+        .stdout("Deploying web to prod\n");
+    Ok(())
+}
+
+#[test]
+fn expand_required_variable() -> TestResult {
+    cmd()?
+        .env_remove("ENV")
+        .args(["--expand", "${ENV:?must be set}"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("Error: ENV: must be set\n");
+    Ok(())
+}
+
+#[test]
+fn template() -> TestResult {
+    cmd()?
+        .env("APP", "api")
+        .env("ENV", "prod")
+        .args(["--template", "tests/inputs/deploy.tmpl"])
+        .assert()
+        .success()
+        .stdout("app=api\nenv=prod\n");
+    Ok(())
+}
+
+#[test]
+fn template_rejects_text_options() -> TestResult {
+    for option in [&["--repeat", "3"][..], &["--quote", "json"], &["--fg", "red"], &["--box"], &["-n"], &["--stdin"]] {
+        cmd()?
+            .args(["--template", "tests/inputs/deploy.tmpl"])
+            .args(option)
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+    Ok(())
+}
+
+#[test]
+fn template_strict() -> TestResult {
+    cmd()?
+        .env_remove("ENV")
+        .args(["--strict", "--template", "tests/inputs/deploy.tmpl"])
+        .assert()
+        .code(1)
+        .stderr("Error: ENV: unbound variable\n");
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("bad.tmpl");
+    fs::write(&path, "app=${APP\n")?;
+    cmd()?
+        .arg("--template")
+        .arg(&path)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::ends_with("bad.tmpl: ${APP\n: bad substitution\n"));
+
+    cmd()?
+        .args(["--template", "tests/inputs/missing.tmpl"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with("Error: tests/inputs/missing.tmpl: "));
+    Ok(())
+}
+
+#[test]
+fn quote() -> TestResult {
+    cmd()?
+        .args(["--quote=shell", "it's", "a b", "plain"])
+        .assert()
+        .success()
+        .stdout("'it'\\''s' 'a b' plain\n");
+
+    cmd()?
+        .args(["--quote", "json", "--separator", ",", "say \"hi\"", "line\nbreak"])
+        .assert()
+        .success()
+        .stdout("\"say \\\"hi\\\"\",\"line\\nbreak\"\n");
+
+    cmd()?
+        .args(["--quote=c", "tab\there"])
+        .assert()
+        .success()
+        .stdout("\"tab\\there\"\n");
+    Ok(())
+}
+
+#[test]
+fn quote_csv_record() -> TestResult {
+    cmd()?
+        .args(["--quote=csv", "name", "a,b", "say \"hi\"", "café"])
+        .assert()
+        .success()
+        .stdout("name,\"a,b\",\"say \"\"hi\"\"\",café\n");
+    Ok(())
+}
+
+#[test]
+fn color_always() -> TestResult {
+    cmd()?
+        .args(["--color=always", "--fg", "red", "--bold", "fail"])
+        .assert()
+        .success()
+        .stdout("\x1b[1;31mfail\x1b[0m\n");
+
+    cmd()?
+        .args(["--color=always", "--markup", "[green]ok[/] done"])
+        .assert()
+        .success()
+        .stdout("\x1b[32mok\x1b[0m done\n");
+    Ok(())
+}
+
+#[test]
+fn color_auto_off_when_piped() -> TestResult {
+    cmd()?
+        .env_remove("NO_COLOR")
+        .args(["--fg", "red", "--markup", "[green]ok[/] done"])
+        .assert()
+        .success()
+        .stdout("ok done\n");
+    Ok(())
+}
+
+#[test]
+fn color_never() -> TestResult {
+    cmd()?
+        .args(["--color", "never", "--bg", "blue", "--underline", "plain"])
+        .assert()
+        .success()
+        .stdout("plain\n");
+    Ok(())
+}
+
+#[test]
+fn brace_expand() -> TestResult {
+    cmd()?
+        .args(["--brace-expand", "file{1..3}.{txt,md}", "{01..10..4}", "{x..z}", "{}"])
+        .assert()
+        .success()
+        .stdout("file1.txt file1.md file2.txt file2.md file3.txt file3.md 01 05 09 x y z {}\n");
+
+    cmd()?
+        .args(["{a,b}"])
+        .assert()
+        .success()
+        .stdout("{a,b}\n");
+    Ok(())
+}
+
+#[test]
+fn from_file_per_line() -> TestResult {
+    cmd()?
+        .args(["--from-file", "tests/inputs/words.txt", "--per-line", "2", "--separator", ","])
+        .assert()
+        .success()
+        .stdout("alpha,beta\ngamma,delta\nepsilon\n");
+    Ok(())
+}
+
+#[test]
+fn stdin_null_delimited() -> TestResult {
+    cmd()?
+        .args(["--stdin", "-0", "first"])
+        .write_stdin("a b\0c\0")
+        .assert()
+        .success()
+        .stdout("first a b c\n");
+    Ok(())
+}
+
+#[test]
+fn per_line_must_be_positive() -> TestResult {
+    cmd()?
+        .args(["--stdin", "--per-line", "0"])
+        .assert()
+        .code(2)
+        .stderr("Error: per-line must be positive\n");
+
+    cmd()?
+        .args(["--from-file", "tests/inputs/missing.txt"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with("Error: tests/inputs/missing.txt: "));
+    Ok(())
+}
+
+#[test]
+fn wrap_and_align() -> TestResult {
+    cmd()?
+        .args(["--width", "12", "--wrap", "--align", "justify", "a bb ccc dddd e ff"])
+        .assert()
+        .success()
+        .stdout("a   bb   ccc\ndddd e ff\n");
+
+    cmd()?
+        .args(["--width", "8", "--align=right", "日本"])
+        .assert()
+        .success()
+        .stdout("    日本\n");
+    Ok(())
+}
+
+#[test]
+fn boxed() -> TestResult {
+    cmd()?
+        .args(["--box", "hi", "there"])
+        .assert()
+        .success()
+        .stdout("┌──────────┐\n│ hi there │\n└──────────┘\n");
+
+    cmd()?
+        .args(["--box=ascii", "--align", "center", "-e", "title\\nmore text"])
+        .assert()
+        .success()
+        .stdout("+-----------+\n|   title   |\n| more text |\n+-----------+\n");
+
+    cmd()?
+        .args(["--box=ascii", "-e", "\\xff"])
+        .assert()
+        .success()
+        .stdout(&b"+---+\n| \xff |\n+---+\n"[..]);
+    Ok(())
+}
+
+#[test]
+fn styled_layout() -> TestResult {
+    cmd()?
+        .args(["--fg", "red", "--color", "always", "--width", "10", "--wrap", "one two three"])
+        .assert()
+        .success()
+        .stdout("\x1b[31mone two\x1b[0m\n\x1b[31mthree\x1b[0m\n");
+
+    cmd()?
+        .args(["--markup", "--color", "always", "--box=ascii", "--width", "10", "--wrap", "[red]one two[/] x"])
+        .assert()
+        .success()
+        .stdout("+--------+\n| \x1b[31mone\x1b[0m    |\n| \x1b[31mtwo\x1b[0m x  |\n+--------+\n");
+    Ok(())
+}
+
+#[test]
+fn encode_and_decode() -> TestResult {
+    cmd()?
+        .args(["--encode", "base64", "-n", "user:secret"])
+        .assert()
+        .success()
+        .stdout("dXNlcjpzZWNyZXQ=");
+
+    cmd()?
+        .args(["--decode=hex", "68 69"])
+        .assert()
+        .success()
+        .stdout("hi\n");
+
+    cmd()?
+        .args(["--encode", "url", "a b&c"])
+        .assert()
+        .success()
+        .stdout("a%20b%26c\n");
+    Ok(())
+}
+
+#[test]
+fn decode_rejects_bad_input() -> TestResult {
+    cmd()?
+        .args(["--decode", "base64", "not base64!"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("Error: invalid base64 input\n");
+    Ok(())
+}
+
+#[test]
+fn to_stderr() -> TestResult {
+    cmd()?
+        .args(["--stderr", "warning"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("warning\n");
+    Ok(())
+}
+
+#[test]
+fn to_append_and_atomic_files() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("out.txt");
+    let path_str = path.to_str().unwrap();
+
+    cmd()?.args(["--to", path_str, "one"]).assert().success().stdout("");
+    cmd()?.args(["--append", path_str, "two"]).assert().success();
+    assert_eq!(fs::read_to_string(&path)?, "one\ntwo\n");
+
+    cmd()?.args(["--atomic", path_str, "three"]).assert().success();
+    assert_eq!(fs::read_to_string(&path)?, "three\n");
+    assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+    Ok(())
+}
+
+#[test]
+fn output_file_errors() -> TestResult {
+    cmd()?
+        .args(["--to", "no/such/dir/out.txt", "x"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with("Error: no/such/dir/out.txt: "));
+
+    cmd()?
+        .args(["--atomic", "no/such/dir/out.txt", "x"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with("Error: no/such/dir/out.txt: "));
+
+    cmd()?
+        .args(["--to", "a", "--append", "b", "x"])
+        .assert()
+        .code(2);
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn write_failure_exits_nonzero() -> TestResult {
+    cmd()?
+        .args(["--append", "/dev/full", "x"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("No space left on device"));
+    Ok(())
+}
+
+#[test]
+fn repeat_and_seq() -> TestResult {
+    cmd()?
+        .args(["--repeat", "2", "hi"])
+        .assert()
+        .success()
+        .stdout("hi\nhi\n");
+
+    cmd()?
+        .args(["--seq", "01..10..4", "file{}.txt"])
+        .assert()
+        .success()
+        .stdout("file01.txt\nfile05.txt\nfile09.txt\n");
+
+    cmd()?
+        .args(["--seq", "1-10", "x"])
+        .assert()
+        .code(2)
+        .stderr("Error: invalid sequence '1-10', expected START..END[..STEP]\n");
+    Ok(())
+}
+
+#[test]
+fn case_and_normalize() -> TestResult {
+    cmd()?
+        .args(["--case", "upper", "straße"])
+        .assert()
+        .success()
+        .stdout("STRASSE\n");
+
+    cmd()?
+        .args(["--case", "lower", "--locale", "tr", "KIRMIZI"])
+        .assert()
+        .success()
+        .stdout("kırmızı\n");
+
+    cmd()?
+        .args(["--case", "kebab", "--normalize", "nfkc", "My ﬁle", "v2Name"])
+        .assert()
+        .success()
+        .stdout("my-file-v2-name\n");
+    Ok(())
+}
+
+#[test]
+fn paced() -> TestResult {
+    let start = std::time::Instant::now();
+    cmd()?
+        .args(["--delay-line", "50ms", "--per-line", "1", "a", "b", "c"])
+        .assert()
+        .success()
+        .stdout("a\nb\nc\n");
+    assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+
+    let start = std::time::Instant::now();
+    cmd()?
+        .args(["--delay-line", "50ms", "--per-line", "1", "--fg", "red", "--color", "always", "a", "b", "c"])
+        .assert()
+        .success()
+        .stdout("\x1b[31ma\x1b[0m\n\x1b[31mb\x1b[0m\n\x1b[31mc\x1b[0m\n");
+    assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+
+    cmd()?
+        .args(["--delay-char", "1ms", "-n", "--fg", "red", "--color", "always", "héllo"])
+        .assert()
+        .success()
+        .stdout("\x1b[31mhéllo\x1b[0m");
+
+    cmd()?
+        .args(["--delay-char", "fast", "x"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("expected a duration such as 20ms"));
+
+    cmd()?
+        .args(["--at", "25:00", "x"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("expected a time as HH:MM:SS or HH:MM"));
+    Ok(())
+}
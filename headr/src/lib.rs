@@ -1,139 +1,195 @@
-use std::{error::Error, io::{BufRead, BufReader, Read, stdin}, fs::File};
-use clap::Parser;
-
-type WrapResult<T> = Result<T, Box<dyn Error>>;
+use std::{ffi::OsString, io::{self, BufRead, Write}};
+use clap::{ArgGroup, CommandFactory, Parser};
+use cmdutil::{for_each_line, open, Defaults, DefaultsArgs, Error, FilesFromArgs, GenerateArgs, InputArgs, Result};
 
 #[derive(Parser)]
 #[derive(Debug)]
+#[command(args_override_self = true)]
+#[command(group(ArgGroup::new("count").multiple(true)))]
 pub struct Config {
-    #[arg(value_name = "FILE NAMES", default_values_t = ["-".to_string()])]
+    #[arg(
+        value_name = "FILE NAMES",
+        default_values_t = ["-".to_string()],
+        conflicts_with_all = ["files0_from", "files_from"],
+    )]
     files: Vec<String>,
 
-    #[arg(short = 'n', long, default_value_t = 10)]
+    #[arg(short = 'n', long, default_value_t = 10, group = "count")]
     lines: usize,
 
-    #[arg(short = 'c', long)]
+    #[arg(short = 'c', long, group = "count")]
     bytes: Option<usize>,
-}
 
-pub fn get_args() -> WrapResult<Config> {
-    let config = Config::parse();
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    files_from: FilesFromArgs,
+
+    #[command(flatten)]
+    defaults: DefaultsArgs,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
 
-    let files = config.files;
-    let lines = config.lines;
-    let bytes = config.bytes;
+pub fn get_args() -> Result<Config> {
+    get_args_from(std::env::args_os())
+}
 
-    if lines == 0 {
-        return Err("lines must be positive".into());
+pub fn get_args_from<I, T>(args: I) -> Result<Config>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let defaults = Defaults::load::<Config>("headr", args)?;
+    let config = Config::parse_from(defaults.args());
+    config.generate.exit_if_requested(Config::command())?;
+    config.defaults.exit_if_print_requested(&defaults, &config)?;
+
+    if config.lines == 0 {
+        return Err(Error::Usage("lines must be positive".to_string()));
     }
 
-    if bytes == Some(0) {
-        return Err("bytes must be positive".into());
+    if config.bytes == Some(0) {
+        return Err(Error::Usage("bytes must be positive".to_string()));
     }
 
-    Ok(Config {
-        files,
-        lines,
-        bytes,
-    })  
+    Ok(config)
 }
 
-pub fn run(config: Config) -> WrapResult<()> {
-    let multiple_file_flag = config.files.len() > 1;
+/// Options for [`head`], independent of the command-line parser.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub lines: usize,
+    pub bytes: Option<usize>,
+}
 
-    for (idx, file) in config.files.iter().enumerate() {
-        let mut last_file_flag = false;
-        if idx == config.files.len() - 1 {
-            last_file_flag = true;
+impl Default for Options {
+    fn default() -> Self {
+        Options { lines: 10, bytes: None }
+    }
+}
+
+impl From<&Config> for Options {
+    fn from(config: &Config) -> Self {
+        Options {
+            lines: config.lines,
+            bytes: config.bytes,
+        }
+    }
+}
+
+pub fn run(config: Config) -> Result<()> {
+    let options = Options::from(&config);
+    let mut stdout = cmdutil::stdout();
+    let mut names = config.files_from.names(&config.files)?.peekable();
+    let mut multiple_file_flag = false;
+    let mut failed = false;
+
+    // Names may be streamed from a list, so look one ahead instead of counting.
+    let mut idx = 0;
+    while let Some(file) = names.next() {
+        let last_file_flag = names.peek().is_none();
+        if idx == 0 {
+            multiple_file_flag = !last_file_flag;
         }
-        match open(file) {
+        idx += 1;
+
+        let file = match file {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("{err}");
+                failed = true;
+                continue;
+            }
+        };
+        match open(&file, config.input.decompress()) {
             Ok(reader) => {
-                if let Err(err) = head(reader, file, config.lines, config.bytes, multiple_file_flag, last_file_flag) {
-                    eprintln!("Error: {err}");
+                if multiple_file_flag {
+                    writeln!(stdout, "==> {file} <==")?;
+                }
+                match head(reader, &mut stdout, &options) {
+                    Err(err) if err.is_broken_pipe() => return Err(err),
+                    Err(err) => {
+                        eprintln!("Error: {err}");
+                        failed = true;
+                    }
+                    Ok(()) => {}
+                }
+                if !last_file_flag {
+                    writeln!(stdout)?;
                 }
             },
             Err(err) => {
-                eprintln!("Failed to open {file}: {err}");
+                eprintln!("Failed to open {err}");
                 eprintln!();
+                failed = true;
             }
         }
+        stdout.flush()?;
     }
-    Ok(())
-}
 
-fn open(filename: &str) -> WrapResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+    if failed {
+        return Err(Error::InputsFailed);
     }
+    Ok(())
 }
 
-fn head(reader: Box<dyn BufRead>, file: &str, lines: usize, bytes: Option<usize>, multiple_file_flag: bool, last_file_flag: bool) -> WrapResult<()> {
-    if multiple_file_flag {
-        println!("==> {file} <==");
+/// Writes the first `options.bytes` bytes of `reader` if set, otherwise the
+/// first `options.lines` lines.
+pub fn head<R: BufRead, W: Write>(reader: R, writer: &mut W, options: &Options) -> Result<()> {
+    match options.bytes {
+        Some(num_bytes) => head_bytes(reader, writer, num_bytes),
+        None => head_lines(reader, writer, options.lines),
     }
-
-    if let Some(num_bytes) = bytes {
-        head_bytes(reader, num_bytes)?;
-    } else {
-        head_lines(reader, lines)?;
-    }
-
-    if !last_file_flag {
-        println!();
-    }
-    
-    Ok(())
 }
 
-fn head_bytes(reader: Box<dyn BufRead>, num_bytes: usize) -> WrapResult<()> {
-    let mut buffer = vec![0; num_bytes];
-    let mut handle = reader.take(num_bytes as u64);
-    let num_bytes_read = handle.read(&mut buffer)?;
-    let content = String::from_utf8_lossy(&buffer[..num_bytes_read]);
-    print!("{content}");
+fn head_bytes<R: BufRead, W: Write>(reader: R, writer: &mut W, num_bytes: usize) -> Result<()> {
+    io::copy(&mut reader.take(num_bytes as u64), writer)?;
     Ok(())
 }
 
-fn head_lines(mut reader: Box<dyn BufRead>, num_lines: usize) -> WrapResult<()> {
-    let mut line = String::new();
-
-    for _ in  0 .. num_lines {
-        let bytes = reader.read_line(&mut line)?;
-        if bytes == 0 {
-            break;
-        }
-        print!("{line}");
-        line.clear();
+fn head_lines<R: BufRead, W: Write>(reader: R, writer: &mut W, num_lines: usize) -> Result<()> {
+    if num_lines == 0 {
+        return Ok(());
     }
 
+    let mut remaining = num_lines;
+    for_each_line(reader, writer, |line, writer| {
+        writer.write_all(line)?;
+        remaining -= 1;
+        Ok(remaining > 0)
+    })?;
+
     Ok(())
 }
 
-fn parse_positive_int(val: &str) -> WrapResult<usize> {
-    match val.parse::<usize>() {
-        Ok(n) if n > 0 => {
-            Ok(n)
-        },
-        _ => Err(val.into()),
-    }
+#[test]
+fn test_head_lines() {
+    let mut out = Vec::new();
+    let options = Options { lines: 2, bytes: None };
+    head(std::io::Cursor::new("a\nb\r\nc\n"), &mut out, &options).unwrap();
+    assert_eq!(out, b"a\nb\r\n");
 }
 
 #[test]
-fn test_parse_positive_int() {
-    let res = parse_positive_int("6");
-    assert!(res.is_ok());
-    assert_eq!(res.unwrap(), 6);
-
-    let res = parse_positive_int("0");
-    assert!(res.is_err());
-    assert_eq!(res.unwrap_err().to_string(), "0");
-
-    let res = parse_positive_int("-1");
-    assert!(res.is_err());
-    assert_eq!(res.unwrap_err().to_string(), "-1");
-
-    let res = parse_positive_int("foo");
-    assert!(res.is_err());
-    assert_eq!(res.unwrap_err().to_string(), "foo");
-}
\ No newline at end of file
+fn test_head_bytes() {
+    let mut out = Vec::new();
+    let options = Options { bytes: Some(3), ..Options::default() };
+    head(std::io::Cursor::new("a\nb\r\nc\n"), &mut out, &options).unwrap();
+    assert_eq!(out, b"a\nb");
+}
+
+#[test]
+fn test_head_raw_bytes() {
+    let mut out = Vec::new();
+    let options = Options { lines: 1, bytes: None };
+    head(std::io::Cursor::new(b"caf\xe9\nx\n"), &mut out, &options).unwrap();
+    assert_eq!(out, b"caf\xe9\n");
+
+    let mut out = Vec::new();
+    let options = Options { bytes: Some(2), ..Options::default() };
+    head(std::io::Cursor::new(b"\xe4\xb8\xad"), &mut out, &options).unwrap();
+    assert_eq!(out, b"\xe4\xb8");
+}
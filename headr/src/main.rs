@@ -1,6 +1,5 @@
 fn main() {
     if let Err(err) = headr::get_args().and_then(headr::run) {
-        eprintln!("Error: {err}");
-        std::process::exit(1);
+        cmdutil::exit(err);
     }
 }
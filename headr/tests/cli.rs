@@ -0,0 +1,110 @@
+use assert_cmd::Command;
+use predicates::prelude::predicate;
+use rand::{distributions::Alphanumeric, Rng};
+use std::error::Error;
+use std::fs;
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+const PRG: &str = "headr";
+const ONE: &str = "tests/inputs/one.txt";
+const THREE: &str = "tests/inputs/three.txt";
+const ONE_GZ: &str = "tests/inputs/one.txt.gz";
+
+/// The tool under test, with neither the developer's own config file nor
+/// `HEADR_OPTS` adding defaults.
+fn cmd() -> Result<Command, Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin(PRG)?;
+    cmd.env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR")).env_remove("HEADR_OPTS");
+    Ok(cmd)
+}
+
+fn gen_bad_file() -> String {
+    loop {
+        let filename: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+
+        if fs::metadata(&filename).is_err() {
+            return filename;
+        }
+    }
+}
+
+#[test]
+fn dies_zero_lines() -> TestResult {
+    cmd()?
+        .args(["-n", "0", ONE])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("lines must be positive"));
+    Ok(())
+}
+
+#[test]
+fn skips_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("{}: .* [(]os error 2[)]", bad);
+
+    cmd()?
+        .args([&bad, ONE])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::is_match(expected)?)
+        .stdout(format!("==> {ONE} <==\none line\n"));
+    Ok(())
+}
+
+#[test]
+fn three_lines() -> TestResult {
+    cmd()?
+        .args(["-n", "2", THREE])
+        .assert()
+        .success()
+        .stdout("three lines\nthree lines\n");
+    Ok(())
+}
+
+#[test]
+fn gzip_file() -> TestResult {
+    cmd()?
+        .arg(ONE_GZ)
+        .assert()
+        .success()
+        .stdout("one line\n");
+    Ok(())
+}
+
+#[test]
+fn gzip_file_no_decompress() -> TestResult {
+    cmd()?
+        .args(["--no-decompress", "-c", "1", ONE_GZ])
+        .assert()
+        .success()
+        .stdout("\x1f");
+    Ok(())
+}
+
+#[test]
+fn files_from_single_name() -> TestResult {
+    cmd()?
+        .args(["--files-from", "-", "-n", "1"])
+        .write_stdin(format!("{THREE}\n"))
+        .assert()
+        .success()
+        .stdout("three lines\n");
+    Ok(())
+}
+
+#[test]
+fn files_from_many_names() -> TestResult {
+    cmd()?
+        .args(["--files-from", "-", "-n", "1"])
+        .write_stdin(format!("{ONE}\n{THREE}\n"))
+        .assert()
+        .success()
+        .stdout(format!("==> {ONE} <==\none line\n\n==> {THREE} <==\nthree lines\n"));
+    Ok(())
+}
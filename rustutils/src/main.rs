@@ -0,0 +1,90 @@
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    io::Write,
+    path::Path,
+};
+
+use cmdutil::{Error, Result};
+
+type Tool = fn(Vec<OsString>) -> Result<()>;
+
+const TOOLS: &[(&str, Tool)] = &[
+    ("catr", |args| catr::get_args_from(args).and_then(catr::run)),
+    ("echor", |args| echor::get_args_from(args).and_then(echor::run)),
+    ("headr", |args| headr::get_args_from(args).and_then(headr::run)),
+    ("uniqr", |args| uniqr::get_args_from(args).and_then(uniqr::run)),
+    ("wcr", |args| wcr::get_args_from(args).and_then(wcr::run)),
+];
+
+fn main() {
+    if let Err(err) = run(env::args_os().collect()) {
+        cmdutil::exit(err);
+    }
+}
+
+fn run(args: Vec<OsString>) -> Result<()> {
+    // Invoked through a symlink such as `wcr -> rustutils`.
+    if let Some(tool) = args.first().and_then(|arg0| find_tool(program_name(arg0))) {
+        return tool(args);
+    }
+
+    match args.get(1).and_then(|arg| arg.to_str()) {
+        Some("--install") => match args.get(2) {
+            Some(dir) => install(Path::new(dir)),
+            None => Err(Error::Usage("--install requires a directory".to_string())),
+        },
+        Some("-h" | "--help") => {
+            let mut stdout = cmdutil::stdout();
+            write!(stdout, "{}", usage())?;
+            stdout.flush()?;
+            Ok(())
+        }
+        Some(name) => match find_tool(name) {
+            Some(tool) => tool(args[1..].to_vec()),
+            None => Err(Error::Usage(format!("unknown tool '{name}'\n\n{}", usage()))),
+        },
+        None => Err(Error::Usage(usage())),
+    }
+}
+
+fn program_name(arg0: &OsStr) -> &str {
+    Path::new(arg0)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+fn find_tool(name: &str) -> Option<Tool> {
+    TOOLS
+        .iter()
+        .find(|(tool_name, _)| *tool_name == name)
+        .map(|(_, tool)| *tool)
+}
+
+fn usage() -> String {
+    let names: Vec<&str> = TOOLS.iter().map(|(name, _)| *name).collect();
+    format!(
+        "Usage: rustutils <TOOL> [ARGS]...\n       rustutils --install <DIR>\n\nTools: {}\n",
+        names.join(", ")
+    )
+}
+
+/// Creates a symlink named after each tool in `dir`, pointing at this binary.
+#[cfg(unix)]
+fn install(dir: &Path) -> Result<()> {
+    let exe = env::current_exe()?;
+    for (name, _) in TOOLS {
+        let link = dir.join(name);
+        std::os::unix::fs::symlink(&exe, &link).map_err(|source| Error::Open {
+            path: link.display().to_string(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn install(_dir: &Path) -> Result<()> {
+    Err(Error::Usage("--install is only supported on Unix".to_string()))
+}
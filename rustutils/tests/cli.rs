@@ -0,0 +1,333 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::predicate;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{Read, Write};
+use std::process::{self, Child, Stdio};
+use tempfile::TempDir;
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+const PRG: &str = "rustutils";
+const FOX: &str = "tests/inputs/fox.txt";
+const DUP: &str = "tests/inputs/dup.txt";
+const TOOLS: [&str; 5] = ["catr", "echor", "headr", "uniqr", "wcr"];
+
+/// The multicall binary, kept from the developer's own defaults.
+fn cmd() -> Result<Command, Box<dyn Error>> {
+    Ok(Command::from_std(isolated(cargo_bin(PRG))))
+}
+
+/// `program` with neither the developer's own config file nor any
+/// `<TOOL>_OPTS` adding defaults.
+fn isolated(program: impl AsRef<OsStr>) -> process::Command {
+    let mut cmd = process::Command::new(program);
+    cmd.env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"));
+    for tool in TOOLS {
+        cmd.env_remove(format!("{}_OPTS", tool.to_uppercase()));
+    }
+    cmd
+}
+
+/// The multicall binary running with `args` and all of its standard
+/// streams piped, for tests that need it alive while they use them.
+fn spawn(args: &[&str]) -> Result<Child, Box<dyn Error>> {
+    let child = isolated(cargo_bin(PRG))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    Ok(child)
+}
+
+/// The first `len` bytes the multicall binary writes for `input` while its
+/// stdin is still open, as when reading from `tail -f`.
+fn output_before_eof(args: &[&str], input: &[u8], len: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut child = spawn(args)?;
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(input)?;
+    stdin.flush()?;
+
+    let mut stdout = child.stdout.take().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut out = vec![0; len];
+        let _ = sender.send(stdout.read_exact(&mut out).map(|_| out));
+    });
+    let out = receiver.recv_timeout(std::time::Duration::from_secs(10));
+    drop(stdin);
+    child.wait()?;
+    Ok(out??)
+}
+
+/// A directory to use as `XDG_CONFIG_HOME`, holding a config file of `text`.
+fn config_dir(text: &str) -> Result<TempDir, Box<dyn Error>> {
+    let dir = TempDir::new()?;
+    fs::create_dir(dir.path().join("rustutils"))?;
+    fs::write(dir.path().join("rustutils/config.toml"), text)?;
+    Ok(dir)
+}
+
+#[test]
+fn dies_no_args() -> TestResult {
+    cmd()?
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Usage: rustutils <TOOL>"));
+    Ok(())
+}
+
+#[test]
+fn dies_unknown_tool() -> TestResult {
+    cmd()?
+        .arg("lsr")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("unknown tool 'lsr'"));
+    Ok(())
+}
+
+#[test]
+fn subcommand_wcr() -> TestResult {
+    cmd()?
+        .args(["wcr", "-l", FOX])
+        .assert()
+        .success()
+        .stdout(format!("       1 {FOX}\n"));
+    Ok(())
+}
+
+#[test]
+fn subcommand_echor() -> TestResult {
+    cmd()?
+        .args(["echor", "-n", "Hello", "there"])
+        .assert()
+        .success()
+        .stdout("Hello there");
+    Ok(())
+}
+
+#[test]
+fn subcommand_uniqr() -> TestResult {
+    cmd()?
+        .args(["uniqr", "-c", DUP])
+        .assert()
+        .success()
+        .stdout("   2 a\n   1 b\n");
+    Ok(())
+}
+
+#[test]
+fn generates_man_and_completions() -> TestResult {
+    for tool in TOOLS {
+        cmd()?
+            .args([tool, "--generate-man"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!(".TH {tool} 1")));
+
+        cmd()?
+            .args([tool, "--generate-completions", "bash"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!("_{tool}()")));
+    }
+    Ok(())
+}
+
+#[test]
+fn installed_symlinks() -> TestResult {
+    let dir = TempDir::new()?;
+    cmd()?
+        .arg("--install")
+        .arg(dir.path())
+        .assert()
+        .success();
+
+    for name in TOOLS {
+        assert!(dir.path().join(name).is_symlink());
+    }
+
+    Command::from_std(isolated(dir.path().join("catr")))
+        .args(["-n", FOX])
+        .assert()
+        .success()
+        .stdout("1 The quick brown fox jumps over the lazy dog.\n");
+
+    Command::from_std(isolated(dir.path().join("headr")))
+        .args(["-c", "9", FOX])
+        .assert()
+        .success()
+        .stdout("The quick");
+    Ok(())
+}
+
+#[test]
+fn quiet_on_broken_pipe() -> TestResult {
+    // Produce more than a pipe buffer of output so the write fails even if
+    // another test's child briefly inherited the read end.
+    let lines = "buffer\n".repeat(100_000);
+    let foxes = [&["wcr"], &[FOX; 5_000][..]].concat();
+    for (args, input) in [(&["catr"][..], lines.as_str()), (&foxes, "")] {
+        let mut child = spawn(args)?;
+        drop(child.stdout.take());
+        let mut stdin = child.stdin.take().unwrap();
+        let _ = stdin.write_all(input.as_bytes());
+        drop(stdin);
+
+        let output = child.wait_with_output()?;
+        assert_eq!(output.status.code(), Some(141), "{}", args[0]);
+        assert!(output.stderr.is_empty(), "{}", args[0]);
+    }
+    Ok(())
+}
+
+#[test]
+fn catr_numbers_stdin_before_eof() -> TestResult {
+    assert_eq!(output_before_eof(&["catr", "-n"], b"hello\n", 8)?, b"1 hello\n");
+    Ok(())
+}
+
+#[test]
+fn headr_reads_stdin_before_eof() -> TestResult {
+    // Not waiting for enough bytes to rule out a compression magic number.
+    assert_eq!(output_before_eof(&["headr", "-n", "2"], b"hello\n", 6)?, b"hello\n");
+    Ok(())
+}
+
+#[test]
+fn headr_config_file_defaults() -> TestResult {
+    let dir = config_dir("[headr]\nlines = 1\n")?;
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .args(["headr", DUP])
+        .assert()
+        .success()
+        .stdout("a\n");
+    Ok(())
+}
+
+#[test]
+fn headr_env_overrides_config_file() -> TestResult {
+    let dir = config_dir("[headr]\nlines = 1\n")?;
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HEADR_OPTS", "-n 2")
+        .args(["headr", DUP])
+        .assert()
+        .success()
+        .stdout("a\na\n");
+    Ok(())
+}
+
+#[test]
+fn headr_command_line_overrides_defaults() -> TestResult {
+    let dir = config_dir("[headr]\nlines = 1\n")?;
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HEADR_OPTS", "-n 2")
+        .args(["headr", "-n", "3", DUP])
+        .assert()
+        .success()
+        .stdout("a\na\nb\n");
+    Ok(())
+}
+
+#[test]
+fn headr_no_config() -> TestResult {
+    let dir = config_dir("[headr]\nlines = 1\n")?;
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HEADR_OPTS", "-n 2")
+        .args(["headr", "--no-config", DUP])
+        .assert()
+        .success()
+        .stdout("a\na\nb\n");
+    Ok(())
+}
+
+#[test]
+fn headr_print_config() -> TestResult {
+    let dir = config_dir("[headr]\nlines = 1\n")?;
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HEADR_OPTS", "-n 2")
+        .args(["headr", "--print-config"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("config.toml: --lines=1\n# HEADR_OPTS: -n 2\n"))
+        .stdout(predicate::str::contains("lines: 2,"));
+    Ok(())
+}
+
+#[test]
+fn headr_dies_bad_config_file() -> TestResult {
+    let dir = config_dir("[headr\n")?;
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .args(["headr", DUP])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("config.toml: "));
+    Ok(())
+}
+
+#[test]
+fn catr_command_line_numbering_replaces_config_numbering() -> TestResult {
+    let dir = config_dir("[catr]\nnumber_lines = true\n")?;
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .args(["catr", "-b"])
+        .write_stdin("a\n\nb\n")
+        .assert()
+        .success()
+        .stdout("1 a\n\n2 b\n");
+    Ok(())
+}
+
+#[test]
+fn catr_command_line_numbering_replaces_env_numbering() -> TestResult {
+    cmd()?
+        .env("CATR_OPTS", "-n")
+        .args(["catr", "--number-nonblank-lines"])
+        .write_stdin("a\n\nb\n")
+        .assert()
+        .success()
+        .stdout("1 a\n\n2 b\n");
+
+    cmd()?
+        .env("CATR_OPTS", "-A")
+        .args(["catr", "-n", "-b", DUP])
+        .assert()
+        .code(2);
+    Ok(())
+}
+
+#[test]
+fn wcr_command_line_counts_replace_default_counts() -> TestResult {
+    let dir = config_dir("[wcr]\nlines = true\n")?;
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .args(["wcr", "-w", FOX])
+        .assert()
+        .success()
+        .stdout(format!("       9 {FOX}\n"));
+
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("WCR_OPTS", "-c --chars")
+        .args(["wcr", "--words", FOX])
+        .assert()
+        .success()
+        .stdout(format!("       9 {FOX}\n"));
+
+    cmd()?
+        .env("XDG_CONFIG_HOME", dir.path())
+        .args(["wcr", FOX])
+        .assert()
+        .success()
+        .stdout(format!("       1 {FOX}\n"));
+    Ok(())
+}
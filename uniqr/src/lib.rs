@@ -0,0 +1,123 @@
+use clap::{CommandFactory, Parser};
+use cmdutil::{create, open, Defaults, DefaultsArgs, GenerateArgs, InputArgs, Result};
+use std::{
+    ffi::OsString,
+    io::{BufRead, Write},
+};
+
+pub fn get_args() -> Result<Args> {
+    get_args_from(std::env::args_os())
+}
+
+pub fn get_args_from<I, T>(args: I) -> Result<Args>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let defaults = Defaults::load::<Args>("uniqr", args)?;
+    let args = Args::parse_from(defaults.args());
+    args.generate.exit_if_requested(Args::command())?;
+    args.defaults.exit_if_print_requested(&defaults, &args)?;
+    Ok(args)
+}
+
+/// Options for [`uniq`], independent of the command-line parser.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    pub count: bool,
+}
+
+impl From<&Args> for Options {
+    fn from(args: &Args) -> Self {
+        Options { count: args.count }
+    }
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let file = open(&args.in_file, args.input.decompress())?;
+    let mut out_file = create(args.out_file.as_deref())?;
+    uniq(file, &mut out_file, &Options::from(&args))?;
+    out_file.flush()?;
+    Ok(())
+}
+
+/// Writes `reader` to `writer` with adjacent duplicate lines collapsed.
+pub fn uniq<R: BufRead, W: Write>(mut file: R, out_file: &mut W, options: &Options) -> Result<()> {
+    let mut print = |count: u64, text: &[u8]| -> Result<()> {
+        if count > 0 {
+            if options.count {
+                write!(out_file, "{:>4} ", count)?;
+            }
+            out_file.write_all(text)?;
+        };
+        Ok(())
+    };
+
+    let mut line = Vec::new();
+    let mut previous = Vec::new();
+    let mut count: u64 = 0;
+    loop {
+        let bytes = file.read_until(b'\n', &mut line)?;
+        if bytes == 0 {
+            break;
+        }
+
+        if line.trim_ascii_end() != previous.trim_ascii_end() {
+            print(count, &previous)?;
+            std::mem::swap(&mut previous, &mut line);
+            count = 0;
+        }
+
+        count += 1;
+        line.clear();
+    }
+    print(count, &previous)?;
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+#[command(author, version, about)]
+#[command(args_override_self = true)]
+pub struct Args {
+    #[arg(value_name = "IN_FILE", default_value = "-")]
+    in_file: String,
+    #[arg(value_name = "OUT_FILE")]
+    out_file: Option<String>,
+    #[arg(short, long)]
+    count: bool,
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    defaults: DefaultsArgs,
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{uniq, Options};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_uniq() {
+        let mut out = Vec::new();
+        uniq(Cursor::new("a\na\nb\na"), &mut out, &Options::default()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "a\nb\na");
+    }
+
+    #[test]
+    fn test_uniq_raw_bytes() {
+        let mut out = Vec::new();
+        uniq(Cursor::new(b"\xe9\n\xe9\n\xff"), &mut out, &Options::default()).unwrap();
+        assert_eq!(out, b"\xe9\n\xff");
+    }
+
+    #[test]
+    fn test_uniq_count() {
+        let mut out = Vec::new();
+        let options = Options { count: true };
+        uniq(Cursor::new("a\na\nb\n"), &mut out, &options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "   2 a\n   1 b\n");
+    }
+}
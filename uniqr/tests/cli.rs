@@ -79,9 +79,17 @@ const T6: Test = Test {
     output_count: "tests/expected/t6.txt.c.out",
 };
 
+/// The tool under test, with neither the developer's own config file nor
+/// `UNIQR_OPTS` adding defaults.
+fn cmd() -> Result<Command, Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(PRG)?;
+    cmd.env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR")).env_remove("UNIQR_OPTS");
+    Ok(cmd)
+}
+
 fn run(test: &Test) -> TestResult {
     let expected = fs::read_to_string(test.output)?;
-    let output = Command::cargo_bin(PRG)?
+    let output = cmd()?
         .arg(test.input)
         .output()
         .expect("fail");
@@ -94,7 +102,7 @@ fn run(test: &Test) -> TestResult {
 
 fn run_count(test: &Test) -> TestResult {
     let expected = fs::read_to_string(test.output_count)?;
-    let output = Command::cargo_bin(PRG)?
+    let output = cmd()?
         .args([test.input, "-c"])
         .output()
         .expect("fail");
@@ -108,7 +116,7 @@ fn run_count(test: &Test) -> TestResult {
 fn run_stdin(test: &Test) -> TestResult {
     let input = fs::read_to_string(test.input)?;
     let expected = fs::read_to_string(test.output)?;
-    let output = Command::cargo_bin(PRG)?
+    let output = cmd()?
         .write_stdin(input)
         .output()
         .expect("fail");
@@ -122,7 +130,7 @@ fn run_stdin(test: &Test) -> TestResult {
 fn run_stdin_count(test: &Test) -> TestResult {
     let input = fs::read_to_string(test.input)?;
     let expected = fs::read_to_string(test.output_count)?;
-    let output = Command::cargo_bin(PRG)?
+    let output = cmd()?
         .arg("--count")
         .write_stdin(input)
         .output()
@@ -139,8 +147,8 @@ fn run_out_file(test: &Test) -> TestResult {
     let out_file = NamedTempFile::new()?;
     let out_path = out_file.path().to_str().unwrap();
 
-    Command::cargo_bin(PRG)?
-        .args(&[test.input, out_path])
+    cmd()?
+        .args([test.input, out_path])
         .assert()
         .success()
         .stdout("");
@@ -155,8 +163,8 @@ fn run_out_file_count(test: &Test) -> TestResult {
     let out_file = NamedTempFile::new()?;
     let out_path = out_file.path().to_str().unwrap();
 
-    Command::cargo_bin(PRG)?
-        .args(&[test.input, out_path, "-c"])
+    cmd()?
+        .args([test.input, out_path, "-c"])
         .assert()
         .success()
         .stdout("");
@@ -172,8 +180,8 @@ fn run_stdin_out_file_count(test: &Test) -> TestResult {
     let out_file = NamedTempFile::new()?;
     let out_path = out_file.path().to_str().unwrap();
 
-    Command::cargo_bin(PRG)?
-        .args(&["-", out_path, "-c"])
+    cmd()?
+        .args(["-", out_path, "-c"])
         .write_stdin(input)
         .assert()
         .success()
@@ -1,41 +1,111 @@
-use std::{error::Error, io::BufRead};
+use std::{
+    ffi::OsString,
+    io::{BufRead, Write},
+    ops::AddAssign,
+};
 
-use clap::Parser;
-use std::io::{stdin, BufReader};
-use std::fs::File;
-
-type WrapResult<T> = Result<T, Box<dyn Error>>;
+use clap::{ArgGroup, CommandFactory, Parser};
+use cmdutil::{open, Defaults, DefaultsArgs, Error, FilesFromArgs, GenerateArgs, InputArgs, Result};
 
 #[derive(Parser)]
 #[derive(Debug)]
+#[command(args_override_self = true)]
+#[command(group(ArgGroup::new("counts").multiple(true)))]
 pub struct Config {
-    #[arg(value_name = "FILE NAMES", default_values_t = ["-".to_string()])]
+    #[arg(
+        value_name = "FILE NAMES",
+        default_values_t = ["-".to_string()],
+        conflicts_with_all = ["files0_from", "files_from"],
+    )]
     files: Vec<String>,
 
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, group = "counts")]
     lines: bool,
 
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, group = "counts")]
     words: bool,
 
-    #[arg(short = 'c', long, default_value_t = false)]
+    #[arg(short = 'c', long, default_value_t = false, group = "counts")]
     bytes: bool,
 
-    #[arg(short = 'm', long, default_value_t = false)]
+    #[arg(short = 'm', long, default_value_t = false, group = "counts")]
     chars: bool,
+
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    files_from: FilesFromArgs,
+
+    #[command(flatten)]
+    defaults: DefaultsArgs,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq)]
 pub struct FileInfo {
-    num_lines: usize,
-    num_words: usize,
-    num_bytes: usize,
-    num_chars: usize,
+    pub num_lines: usize,
+    pub num_words: usize,
+    pub num_bytes: usize,
+    pub num_chars: usize,
+}
+
+impl AddAssign<&FileInfo> for FileInfo {
+    fn add_assign(&mut self, other: &FileInfo) {
+        self.num_lines += other.num_lines;
+        self.num_words += other.num_words;
+        self.num_bytes += other.num_bytes;
+        self.num_chars += other.num_chars;
+    }
+}
+
+/// Which counts to report, independent of the command-line parser.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub lines: bool,
+    pub words: bool,
+    pub bytes: bool,
+    pub chars: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            lines: true,
+            words: true,
+            bytes: true,
+            chars: false,
+        }
+    }
 }
 
-pub fn get_args() -> WrapResult<Config> {
-    let config = Config::parse();
-    let (mut lines, mut words, mut bytes, mut chars) = (
+impl From<&Config> for Options {
+    fn from(config: &Config) -> Self {
+        Options {
+            lines: config.lines,
+            words: config.words,
+            bytes: config.bytes,
+            chars: config.chars,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config> {
+    get_args_from(std::env::args_os())
+}
+
+pub fn get_args_from<I, T>(args: I) -> Result<Config>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let defaults = Defaults::load::<Config>("wcr", args)?;
+    let config = Config::parse_from(defaults.args());
+    config.generate.exit_if_requested(Config::command())?;
+    config.defaults.exit_if_print_requested(&defaults, &config)?;
+    let (mut lines, mut words, mut bytes, chars) = (
         config.lines,
         config.words,
         config.bytes,
@@ -50,7 +120,7 @@ pub fn get_args() -> WrapResult<Config> {
     }
 
     if bytes && chars {
-        return Err("The argument '--bytes' cannot be used with '--chars'".into());
+        return Err(Error::Usage("The argument '--bytes' cannot be used with '--chars'".to_string()));
     }
 
     Ok(Config {
@@ -62,24 +132,25 @@ pub fn get_args() -> WrapResult<Config> {
     })
 }
 
-fn count(mut file: impl BufRead) -> WrapResult<FileInfo> {
+pub fn count<R: BufRead>(mut file: R) -> Result<FileInfo> {
     let mut num_lines = 0;
     let mut num_words = 0;
     let mut num_bytes = 0;
     let mut num_chars = 0;
 
-    let mut line = String::new();
+    let mut line = Vec::new();
     loop {
-        let line_bytes = file.read_line(&mut line)?;
+        let line_bytes = file.read_until(b'\n', &mut line)?;
         if line_bytes == 0 {
             break;
         }
 
         num_bytes += line_bytes;
         num_lines += 1;
-        num_words += line.split_whitespace().count();
-        num_chars += line.chars().count();
-        
+        let (words, chars) = count_words_and_chars(&line);
+        num_words += words;
+        num_chars += chars;
+
         line.clear();
     }
 
@@ -91,49 +162,101 @@ fn count(mut file: impl BufRead) -> WrapResult<FileInfo> {
     })
 }
 
-pub fn run(config: Config) -> WrapResult<()> {
-    let mut total_lines = 0;
-    let mut total_words = 0;
-    let mut total_bytes = 0;
-    let mut total_chars = 0;
+/// Decodes `line` as UTF-8 to count words and characters. Invalid bytes are
+/// not characters but do belong to words, so binary data still gets counted.
+fn count_words_and_chars(line: &[u8]) -> (usize, usize) {
+    let mut num_words = 0;
+    let mut num_chars = 0;
+    let mut in_word = false;
 
-    for file in &config.files {
-        match open(file) {
-            Err(err) => eprintln!("{}: {}", file, err),
+    for chunk in line.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            num_chars += 1;
+            if c.is_whitespace() {
+                in_word = false;
+            } else if !in_word {
+                num_words += 1;
+                in_word = true;
+            }
+        }
+        if !chunk.invalid().is_empty() && !in_word {
+            num_words += 1;
+            in_word = true;
+        }
+    }
+
+    (num_words, num_chars)
+}
+
+/// Counts `reader` and writes one line of counts to `writer`, followed by
+/// `name` if given.
+pub fn wc<R: BufRead, W: Write>(reader: R, writer: &mut W, options: &Options, name: Option<&str>) -> Result<FileInfo> {
+    let file_info = count(reader)?;
+    write_counts(writer, &file_info, options, name)?;
+    Ok(file_info)
+}
+
+pub fn write_counts<W: Write>(writer: &mut W, file_info: &FileInfo, options: &Options, name: Option<&str>) -> Result<()> {
+    writeln!(
+        writer,
+        "{}{}{}{}{}",
+        format_field(file_info.num_lines, options.lines),
+        format_field(file_info.num_words, options.words),
+        format_field(file_info.num_bytes, options.bytes),
+        format_field(file_info.num_chars, options.chars),
+        match name {
+            Some(name) => format!(" {}", name),
+            None => "".to_string(),
+        }
+    )?;
+    Ok(())
+}
+
+pub fn run(config: Config) -> Result<()> {
+    let options = Options::from(&config);
+    let mut stdout = cmdutil::stdout();
+    let mut total = FileInfo::default();
+    let mut num_files = 0;
+    let mut failed = false;
+
+    for file in config.files_from.names(&config.files)? {
+        num_files += 1;
+        let file = match file {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("{}", err);
+                failed = true;
+                continue;
+            }
+        };
+        match open(&file, config.input.decompress()) {
+            Err(err) => {
+                eprintln!("{}", err);
+                failed = true;
+            }
             Ok(file_reader) => {
-                if let Ok(file_info) = count(file_reader) {
-                    println!(
-                        "{}{}{}{}{}",
-                        format_field(file_info.num_lines, config.lines),
-                        format_field(file_info.num_words, config.words),
-                        format_field(file_info.num_bytes, config.bytes),
-                        format_field(file_info.num_chars, config.chars),
-                        if file == "-" {
-                            "".to_string()
-                        } else {
-                            format!(" {}", file)
-                        }
-                    );
-
-                    total_lines += file_info.num_lines;
-                    total_words += file_info.num_words;
-                    total_bytes += file_info.num_bytes;
-                    total_chars += file_info.num_chars;
+                let name = if file == "-" { None } else { Some(file.as_str()) };
+                match wc(file_reader, &mut stdout, &options, name) {
+                    Err(err) if err.is_broken_pipe() => return Err(err),
+                    Err(err) => {
+                        eprintln!("{}: {}", file, err);
+                        failed = true;
+                    }
+                    Ok(file_info) => total += &file_info,
                 }
             }
         }
+        stdout.flush()?;
     }
 
-    if config.files.len() > 1 {
-        println!(
-            "{}{}{}{} total",
-            format_field(total_lines, config.lines),
-            format_field(total_words, config.words),
-            format_field(total_bytes, config.bytes),
-            format_field(total_chars, config.chars),
-        );
+    if num_files > 1 {
+        write_counts(&mut stdout, &total, &options, Some("total"))?;
+        stdout.flush()?;
     }
 
+    if failed {
+        return Err(Error::InputsFailed);
+    }
     Ok(())
 }
 
@@ -145,16 +268,9 @@ fn format_field(value: usize, show: bool) -> String {
     }
 }
 
-fn open(file: &str) -> WrapResult<Box<dyn BufRead>> {
-    match file {
-        "-" => Ok(Box::new(BufReader::new(stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(file)?))),
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use super::{count, format_field, FileInfo};
+    use super::{count, format_field, wc, FileInfo, Options};
     use std::io::Cursor;
 
     #[test]
@@ -171,6 +287,27 @@ mod tests {
         assert_eq!(info.unwrap(), expected);
     }
 
+    #[test]
+    fn test_count_invalid_utf8() {
+        let info = count(Cursor::new(b"caf\xe9 ol\xc3\xa9\n\xff\xfe\n"));
+        let expected = FileInfo {
+            num_lines: 2,
+            num_words: 3,
+            num_chars: 9,
+            num_bytes: 13,
+        };
+        assert_eq!(info.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_wc() {
+        let mut out = Vec::new();
+        let options = Options { words: false, ..Options::default() };
+        let info = wc(Cursor::new("a b\nc\n"), &mut out, &options, Some("x.txt"));
+        assert_eq!(info.unwrap().num_words, 3);
+        assert_eq!(String::from_utf8(out).unwrap(), "       2       6 x.txt\n");
+    }
+
     #[test]
     fn test_format_field() {
         assert_eq!(format_field(1, false), "");
@@ -1,6 +1,5 @@
 fn main() {
     if let Err(err) = wcr::get_args().and_then(wcr::run) {
-        eprintln!("Error: {}", err);
-        std::process::exit(1);
+        cmdutil::exit(err);
     }
 }
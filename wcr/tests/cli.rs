@@ -10,6 +10,14 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
 
+/// The tool under test, with neither the developer's own config file nor
+/// `WCR_OPTS` adding defaults.
+fn cmd() -> Result<Command, Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(PRG)?;
+    cmd.env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR")).env_remove("WCR_OPTS");
+    Ok(cmd)
+}
+
 fn gen_bad_file() -> String {
     loop {
         let filename = rand::thread_rng()
@@ -26,10 +34,10 @@ fn gen_bad_file() -> String {
 
 #[test]
 fn dies_chars_and_bytes() -> TestResult {
-    Command::cargo_bin(PRG)?
-        .args(&["-m", "-c"])
+    cmd()?
+        .args(["-m", "-c"])
         .assert()
-        .failure()
+        .code(2)
         .stderr(predicate::str::contains(
             "The argument '--bytes' cannot be used with '--chars'",
         ));
@@ -38,7 +46,7 @@ fn dies_chars_and_bytes() -> TestResult {
 
 fn run(args: &[&str], expected_file: &str) -> TestResult {
     let expected = fs::read_to_string(expected_file)?;
-    Command::cargo_bin(PRG)?
+    cmd()?
         .args(args)
         .assert()
         .success()
@@ -50,14 +58,71 @@ fn run(args: &[&str], expected_file: &str) -> TestResult {
 fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
-    Command::cargo_bin(PRG)?
+    cmd()?
         .arg(bad)
         .assert()
-        .success()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+#[test]
+fn counts_after_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    cmd()?
+        .args([&bad, FOX])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::starts_with(expected.trim_end()));
+    Ok(())
+}
+
+#[test]
+fn files0_from_stdin() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    cmd()?
+        .args(["--files0-from", "-"])
+        .write_stdin(format!("{EMPTY}\0{FOX}\0{ATLAMAL}\0"))
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+#[test]
+fn files0_from_empty_name() -> TestResult {
+    cmd()?
+        .args(["--files0-from", "-"])
+        .write_stdin(format!("{FOX}\0\0"))
+        .assert()
+        .code(1)
+        .stderr("-:2: invalid zero-length file name\n");
+    Ok(())
+}
+
+#[test]
+fn files0_from_stdin_rejects_stdin() -> TestResult {
+    cmd()?
+        .args(["--files0-from", "-"])
+        .write_stdin(format!("-\0{FOX}\0"))
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(FOX))
+        .stderr("-:1: when reading file names from standard input, no file name of '-' allowed\n");
+    Ok(())
+}
+
+#[test]
+fn dies_files0_from_and_files() -> TestResult {
+    cmd()?
+        .args(["--files0-from", "-", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
 #[test]
 fn empty() -> TestResult {
     run(&[EMPTY], "tests/expected/empty.txt.out")
@@ -103,6 +168,35 @@ fn fox_bytes_lines() -> TestResult {
     run(&["-l", "-c", FOX], "tests/expected/fox.txt.cl.out")
 }
 
+#[test]
+fn fox_compressed() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    for file in ["tests/inputs/fox.txt.xz", "tests/inputs/fox.txt.bz2"] {
+        cmd()?
+            .arg(file)
+            .assert()
+            .success()
+            .stdout(expected.replace(FOX, file));
+    }
+    Ok(())
+}
+
+#[test]
+fn latin1() -> TestResult {
+    let file = "tests/inputs/latin1.txt";
+    cmd()?
+        .arg(file)
+        .assert()
+        .success()
+        .stdout(format!("       2       2      12 {file}\n"));
+    cmd()?
+        .args(["-m", file])
+        .assert()
+        .success()
+        .stdout(format!("      10 {file}\n"));
+    Ok(())
+}
+
 #[test]
 fn atlamal() -> TestResult {
     run(&[ATLAMAL], "tests/expected/atlamal.txt.out")
@@ -143,7 +237,7 @@ fn atlamal_stdin() -> TestResult {
     let input = fs::read_to_string(ATLAMAL)?;
     let expected =
         fs::read_to_string("tests/expected/atlamal.txt.stdin.out")?;
-    Command::cargo_bin(PRG)?
+    cmd()?
         .write_stdin(input)
         .assert()
         .stdout(expected);